@@ -1,65 +1,119 @@
-use ariadne::{Cache, Color, Config as ErrorConfig, Label, Report, ReportKind, Source};
+mod verify;
 
-use clap::{App, Arg};
+use ariadne::{
+    Cache, Color, Config as ErrorConfig, Label, Report, ReportBuilder, ReportKind, Source,
+};
+
+use clap::{App, AppSettings, Arg};
 
-use lang::core::{error::Errors, span::SourceMap};
-use lang::OptLevel;
+use serde::Deserialize;
+
+use lang::core::{
+    error::Errors,
+    span::{SourceMap, Span, SpanOrigin},
+};
 use lang::{
-    core::error::{Error, Pattern, Reason},
+    core::diagnostic::{
+        count_arguments, display_as_choice, generate_cause, link_reason_note, Diagnostic,
+    },
+    core::error::{Error, Reason, Warning},
+    core::explanations,
+    core::progress::{self, Progress},
     core::types,
 };
+use lang::{middle_end::builtin, OptLevel, ProjectKind, LANGUAGE_VERSION, VERSION};
 
 use internment::Intern;
 
 use camino::Utf8PathBuf;
 
+use std::backtrace::Backtrace;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::fmt::Display as FmtDisplay;
-use std::io::Error as IOError;
-use std::io::ErrorKind;
-use std::path::PathBuf;
+use std::ops::Range;
+use std::panic;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
-fn display_as_choice(list: &[Pattern]) -> String {
-    format!(
-        "{}{}",
-        list[..list.len() - 1]
-            .iter()
-            .map(|pattern| pattern.to_string())
-            .collect::<Vec<String>>()
-            .join(", "),
-        if list.len() > 1 {
-            format!(", or {}", list.last().unwrap())
-        } else {
-            list.first().unwrap().to_string()
-        }
-    )
+fn display_basic_error(message: String, code: Option<&str>) {
+    match code {
+        Some(code) => eprintln!(
+            "{} {}",
+            Color::Red.paint(format!("[{}] Error:", code)),
+            message
+        ),
+        None => eprintln!("{} {}", Color::Red.paint("Error:"), message),
+    }
 }
 
-fn display_basic_error(message: String) {
-    eprintln!("{} {}", Color::Red.paint("Error:"), message);
-}
+type ReportSpan = (Intern<Utf8PathBuf>, Range<usize>);
 
-// This exists as a bypass to the orphan rule.
-struct ErrorSources(HashMap<Intern<Utf8PathBuf>, Source>);
+// This exists as a bypass to the orphan rule. The original contents are kept so that errors can quote them. The
+// expressions that labelled code was generated from are collected while a report is built, and are labelled at it's end.
+struct ErrorSources(
+    HashMap<Intern<Utf8PathBuf>, Source>,
+    SourceMap,
+    Vec<ReportSpan>,
+);
 
 impl ErrorSources {
     fn from(sources: SourceMap) -> Self {
         Self(
             sources
                 .0
-                .into_iter()
-                // A space is currently added to each source since ariadne doesn't handle empty files.
+                .iter()
+                // A space is currently added to each source since ariadne doesn't handle empty files. A `\r` before a
+                // line break is displayed as a space, which keeps the offsets of the spans into the source the same.
                 .map(|(path, contents)| {
                     (
-                        path,
-                        Source::from(if contents.is_empty() { " " } else { &contents }),
+                        *path,
+                        Source::from(if contents.is_empty() {
+                            String::from(" ")
+                        } else {
+                            contents.replace('\r', " ")
+                        }),
                     )
                 })
                 .collect(),
+            sources,
+            Vec::new(),
         )
     }
+
+    fn label(&mut self, span: &Span) -> Label<ReportSpan> {
+        if let SpanOrigin::Generated { from } = &span.origin {
+            if !self.2.contains(&(span.path, from.clone())) {
+                self.2.push((span.path, from.clone()));
+            }
+        }
+
+        Label::new((span.path, span.range.clone()))
+    }
+
+    // Only snippets on a single line are quoted, longer ones are better shown by the labels of the report.
+    fn quote(&self, span: &Span) -> Option<String> {
+        self.1
+            .snippet(span)
+            .filter(|snippet| !snippet.contains('\n'))
+            .map(|snippet| Color::Green.paint(format!("`{}`", snippet)).to_string())
+    }
+}
+
+trait WithOrigins {
+    fn with_origins(self, cache: &mut ErrorSources) -> Self;
+}
+
+impl WithOrigins for ReportBuilder<ReportSpan> {
+    fn with_origins(mut self, cache: &mut ErrorSources) -> Self {
+        self.add_labels(cache.2.drain(..).map(|origin| {
+            Label::new(origin)
+                .with_message("In code generated from this expression.")
+                .with_color(Color::Blue)
+        }));
+
+        self
+    }
 }
 
 impl Cache<Intern<Utf8PathBuf>> for ErrorSources {
@@ -72,45 +126,159 @@ impl Cache<Intern<Utf8PathBuf>> for ErrorSources {
     }
 }
 
-fn generate_cause(error: IOError) -> String {
-    format!(
-        "{} (error no. {})",
-        match error.kind() {
-            ErrorKind::NotFound => "it was not found",
-            ErrorKind::PermissionDenied => "permission was denied",
-            ErrorKind::Interrupted => "the operation was interrupted",
-            _ => unreachable!(),
-        },
-        error
-            .raw_os_error()
-            .map_or(String::from("?"), |os_error| os_error.to_string())
-    )
+trait Display {
+    fn display(self, cache: &mut ErrorSources, config: ErrorConfig);
 }
 
-trait Display {
-    fn display(self, cache: &mut ErrorSources, compact: bool);
+impl Display for Warning {
+    fn display(self, cache: &mut ErrorSources, config: ErrorConfig) {
+        let code = self.code();
+
+        match self {
+            Warning::ShadowsFunction {
+                name,
+                original,
+                shadow,
+            } => Report::build(ReportKind::Warning, shadow.path, shadow.range.start)
+                .with_message(format!(
+                    "the variable {} shadows a function.",
+                    Color::Green.paint(name)
+                ))
+                .with_label(
+                    cache
+                        .label(&shadow)
+                        .with_message("The variable is declared here.")
+                        .with_color(Color::Yellow),
+                )
+                .with_label(
+                    cache
+                        .label(&original)
+                        .with_message("This function can't be used by it's name after it.")
+                        .with_color(Color::Blue),
+                )
+                .with_note("Rename the variable if the function is still needed."),
+            Warning::ConstantCondition { span, value } => {
+                Report::build(ReportKind::Warning, span.path, span.range.start)
+                    .with_message("this condition is constant.")
+                    .with_label(
+                        cache
+                            .label(&span)
+                            .with_message(format!("This is always {}.", Color::Cyan.paint(value)))
+                            .with_color(Color::Yellow),
+                    )
+                    .with_note(format!(
+                        "Only the {} branch is ever taken.",
+                        if value { "first" } else { "second" }
+                    ))
+            }
+            Warning::TypeQuery { span, data_type } => {
+                Report::build(ReportKind::Warning, span.path, span.range.start)
+                    .with_message(format!(
+                        "this value is of type {}.",
+                        Color::Cyan.paint(&data_type)
+                    ))
+                    .with_label(
+                        cache
+                            .label(&span)
+                            .with_message(format!(
+                                "{} is inferred to be {}.",
+                                cache.quote(&span).unwrap_or_else(|| String::from("This")),
+                                Color::Cyan.paint(&data_type)
+                            ))
+                            .with_color(Color::Yellow),
+                    )
+                    .with_note("Remove the `@typeof` once it isn't needed.")
+            }
+            Warning::UnreachableBranch { span } => {
+                Report::build(ReportKind::Warning, span.path, span.range.start)
+                    .with_message("this branch is unreachable.")
+                    .with_label(
+                        cache
+                            .label(&span)
+                            .with_message("This is never taken.")
+                            .with_color(Color::Yellow),
+                    )
+                    .with_note(
+                        "The condition before it is always true, so remove the branch or fix the condition.",
+                    )
+            }
+        }
+        .with_origins(cache)
+        .with_code(code)
+        .with_config(config)
+        .finish()
+        .eprint(cache)
+        .unwrap()
+    }
 }
 
 impl Display for Error {
-    fn display(self, cache: &mut ErrorSources, compact: bool) {
+    fn display(self, cache: &mut ErrorSources, config: ErrorConfig) {
+        let code = self.code();
+
         match self {
-            Error::Basic(context) => display_basic_error(format!("{}.", context)),
-            Error::IO { error, action } => display_basic_error(format!(
-                "failed to {} because {}.",
-                action,
-                generate_cause(error)
-            )),
-            Error::ConflictingModuleNames { parent, name } => display_basic_error(format!(
-                "The child module {} exists more than once in the parent module {}.",
-                Color::Green.paint(parent),
-                Color::Green.paint(name)
-            )),
+            Error::Basic(context) => display_basic_error(format!("{}.", context), code),
+            Error::IO { error, action } => display_basic_error(
+                format!("failed to {} because {}.", action, generate_cause(error)),
+                code,
+            ),
+            Error::ConflictingModuleNames {
+                parent,
+                name,
+                first,
+                second,
+            } => display_basic_error(
+                format!(
+                    "The child module {} exists more than once in the parent module {}, at {} and at {}. One of them must be renamed.",
+                    Color::Green.paint(name),
+                    Color::Green.paint(parent),
+                    Color::Green.paint(first),
+                    Color::Green.paint(second)
+                ),
+                code,
+            ),
+            Error::MissingMain => display_basic_error(
+                String::from(
+                    "the project has no `main` function in it's entry module. Use `--lib` if the project isn't meant to be executed.",
+                ),
+                code,
+            ),
+            Error::ModuleCycle { path, target } => display_basic_error(
+                format!(
+                    "the directory {} is a link to {}, which contains it. It's skipped, since it's modules would contain themselves.",
+                    Color::Green.paint(path),
+                    Color::Green.paint(target)
+                ),
+                code,
+            ),
+            Error::MissingEntryPoint { expected } => display_basic_error(
+                format!(
+                    "the project has no entry point, expected it at {}. A project is a directory whose root module is a `main.bell` file, with every other file or directory in it being a submodule.",
+                    Color::Green.paint(expected)
+                ),
+                code,
+            ),
+            // This is the only error rendered as a report that doesn't have a code.
+            Error::BasicAt { message, span } => {
+                Report::build(ReportKind::Error, span.path, span.range.start)
+                    .with_message(format!("{}.", message))
+                    .with_label(
+                        cache.label(&span)
+                            .with_message("Here.")
+                            .with_color(Color::Red),
+                    )
+                    .with_origins(cache)
+                    .with_config(config)
+                    .finish()
+                    .eprint(cache)
+                    .unwrap()
+            }
             _ => match self {
                 Error::UnterminatedBlockComment { span } => {
                     Report::build(ReportKind::Error, span.path, span.range.start)
                         .with_message("unterminated block comment.")
                         .with_label(
-                            Label::new((span.path, span.range))
+                            cache.label(&span)
                                 .with_message("A termination is needed somewhere in this range.")
                                 .with_color(Color::Red),
                         )
@@ -123,7 +291,7 @@ impl Display for Error {
                     Report::build(ReportKind::Error, span.path, span.range.start)
                         .with_message("unterminated string.")
                         .with_label(
-                            Label::new((span.path, span.range))
+                            cache.label(&span)
                                 .with_message("A termination is needed somewhere in this range.")
                                 .with_color(Color::Red),
                         )
@@ -132,6 +300,93 @@ impl Display for Error {
                             Color::Green.paint('"')
                         ))
                 }
+                Error::ReservedTypeName { name, span } => {
+                    Report::build(ReportKind::Error, span.path, span.range.start)
+                        .with_message(format!(
+                            "{} is the name of a type, so it can't name a variable.",
+                            Color::Green.paint(name)
+                        ))
+                        .with_label(
+                            cache.label(&span)
+                                .with_message("This name is reserved for a primitive type.")
+                                .with_color(Color::Red),
+                        )
+                        .with_note(
+                            "Type hints would still refer to the type, so give the variable another name.",
+                        )
+                }
+                Error::ConstantOverflow { span } => {
+                    Report::build(ReportKind::Error, span.path, span.range.start)
+                        .with_message("this arithmetic overflows.")
+                        .with_label(
+                            cache.label(&span)
+                                .with_message("The result of this doesn't fit in an integer.")
+                                .with_color(Color::Red),
+                        )
+                        .with_note("Integers range from -2147483648 to 2147483647, inclusive.")
+                }
+                Error::InfiniteType { span } => {
+                    Report::build(ReportKind::Error, span.path, span.range.start)
+                        .with_message("this type would contain itself.")
+                        .with_label(
+                            cache.label(&span)
+                                .with_message("The type of this is used as part of itself.")
+                                .with_color(Color::Red),
+                        )
+                        .with_note(
+                            "A value can't be used as part of itself, give it a type explicitly to find the mistake.",
+                        )
+                }
+                Error::MissingFunctionBody { function_span } => Report::build(
+                    ReportKind::Error,
+                    function_span.path,
+                    function_span.range.start,
+                )
+                .with_message("this function has no body.")
+                .with_label(
+                    cache
+                        .label(&function_span)
+                        .with_message("A block was expected after this.")
+                        .with_color(Color::Red),
+                )
+                .with_note(format!(
+                    "Add a body after the function, such as {}.",
+                    Color::Green.paint("`{}`")
+                )),
+                Error::Unsupported { construct, span } => {
+                    Report::build(ReportKind::Error, span.path, span.range.start)
+                        .with_message(format!("{} isn't supported yet.", construct))
+                        .with_label(
+                            cache.label(&span)
+                                .with_message("This can't be compiled yet.")
+                                .with_color(Color::Red),
+                        )
+                        .with_note(
+                            "The compiler can't generate code for this yet, so it must be written another way.",
+                        )
+                }
+                Error::ChainedComparison { span } => {
+                    Report::build(ReportKind::Error, span.path, span.range.start)
+                        .with_message("comparison operators cannot be chained.")
+                        .with_label(
+                            cache.label(&span)
+                                .with_message("The left side of the last comparison is itself a comparison.")
+                                .with_color(Color::Red),
+                        )
+                        .with_note(format!(
+                            "Compare each pair on it's own, such as {}.",
+                            Color::Green.paint("`a < b && b < c`")
+                        ))
+                }
+                Error::AssertionFailed { span } => {
+                    Report::build(ReportKind::Error, span.path, span.range.start)
+                        .with_message("assertion is always false.")
+                        .with_label(
+                            cache.label(&span)
+                                .with_message("This condition is never true.")
+                                .with_color(Color::Red),
+                        )
+                }
                 Error::Unexpected {
                     expected,
                     found,
@@ -158,14 +413,14 @@ impl Display for Error {
                                 format!("{}.", message)
                             })
                             .with_label(
-                                Label::new((found.span.path, found.span.range))
+                                cache.label(&found.span)
                                     .with_message("Here.")
                                     .with_color(Color::Red),
                             );
 
                     if let Reason::UnclosedDelimiter(delimiter) = reason {
                         initial = initial.with_label(
-                            Label::new((delimiter.span.path, delimiter.span.range))
+                            cache.label(&delimiter.span)
                                 .with_message("Because of this delimiter.")
                                 .with_color(Color::Blue),
                         )
@@ -180,7 +435,7 @@ impl Display for Error {
                             Color::Green.paint(id.value)
                         ))
                         .with_label(
-                            Label::new((id.span.path, id.span.range))
+                            cache.label(&id.span)
                                 .with_message("Here.")
                                 .with_color(Color::Red),
                         )
@@ -192,12 +447,12 @@ impl Display for Error {
                             Color::Green.paint(id)
                         ))
                         .with_label(
-                            Label::new((first.path, first.range))
+                            cache.label(&first)
                                 .with_message("The first identifier is declared here.")
                                 .with_color(Color::Blue),
                         )
                         .with_label(
-                            Label::new((second.path, second.range))
+                            cache.label(&second)
                                 .with_message(
                                     "The second identifier is declared here, in the same scope.",
                                 )
@@ -235,53 +490,13 @@ impl Display for Error {
                             Color::Magenta.paint(&b.value)
                         ));
 
-                    match reason {
-                        types::LinkReason::Other => (),
-                        _ => {
-                            report = report.with_note(match reason {
-                                types::LinkReason::Assign => {
-                                    "Assignments cannot change the type of a memory location. You \
-                                     must shadow it in order to do that."
-                                }
-                                types::LinkReason::Condition => {
-                                    "This condition needs to return a boolean."
-                                }
-                                types::LinkReason::Conditional => {
-                                    "All branches in a conditional expression must return the same \
-                                     type."
-                                }
-                                types::LinkReason::Field => {
-                                    "A field must be assigned a value of it's compatible type."
-                                }
-                                types::LinkReason::Loop => {
-                                    "Every break from a loop must be of the same type."
-                                }
-                                types::LinkReason::Parameter => {
-                                    "A function must be called with arguments of matching types."
-                                }
-                                types::LinkReason::Return => {
-                                    "All returns from a function must be of the same type."
-                                }
-                                types::LinkReason::Structure => {
-                                    "You must fill out all fields of a structure when constructing \
-                                     it."
-                                }
-                                types::LinkReason::Call => {
-                                    "When calling a function, you must specify the values for it's \
-                                     exact number of arguments."
-                                }
-                                types::LinkReason::Declaration => {
-                                    "The type hint of this declaration doesn't match the value of \
-                                     it."
-                                }
-                                _ => unreachable!(),
-                            })
-                        }
+                    if let Some(note) = link_reason_note(reason) {
+                        report = report.with_note(note);
                     }
 
                     if let Some(span) = a.span {
                         report = report.with_label(
-                            Label::new((span.path, span.range.clone()))
+                            cache.label(&span)
                                 .with_color(Color::Cyan)
                                 .with_message(format!(
                                     "This is of type {}.",
@@ -296,7 +511,7 @@ impl Display for Error {
                     }) {
                         let span = element.data_type.span.as_ref().unwrap();
                         report = report.with_label(
-                            Label::new((span.path, span.range.clone()))
+                            cache.label(span)
                                 .with_color(Color::Cyan)
                                 .with_message(format!(
                                     "Because this is of type {} and {}.",
@@ -308,7 +523,7 @@ impl Display for Error {
 
                     if let Some(span) = b.span {
                         report = report.with_label(
-                            Label::new((span.path, span.range.clone()))
+                            cache.label(&span)
                                 .with_color(Color::Magenta)
                                 .with_message(format!(
                                     "This is of type {}.",
@@ -323,7 +538,7 @@ impl Display for Error {
                     }) {
                         let span = element.data_type.span.as_ref().unwrap();
                         report = report.with_label(
-                            Label::new((span.path, span.range.clone()))
+                            cache.label(span)
                                 .with_color(Color::Magenta)
                                 .with_message(format!(
                                     "Because this is of type {} and {}.",
@@ -335,6 +550,161 @@ impl Display for Error {
 
                     report
                 }
+                Error::UnwrappedOptional(value) => Report::build(
+                    ReportKind::Error,
+                    value.span.path,
+                    value.span.range.start,
+                )
+                .with_message(format!(
+                    "Value of type {} may be null.",
+                    Color::Cyan.paint(&value.value)
+                ))
+                .with_label(
+                    cache.label(&value.span)
+                        .with_message("This is used without checking that it isn't null.")
+                        .with_color(Color::Red),
+                )
+                .with_note("Use `if var name = value { ... }` to access the value when it isn't null."),
+                Error::UnknownLabel(label) => Report::build(
+                    ReportKind::Error,
+                    label.span.path,
+                    label.span.range.start,
+                )
+                .with_message(format!(
+                    "Label {} doesn't exist.",
+                    Color::Green.paint(format!("'{}", label.value))
+                ))
+                .with_label(
+                    cache.label(&label.span)
+                        .with_message("There is no surrounding loop with this label.")
+                        .with_color(Color::Red),
+                )
+                .with_note("A loop is labeled by writing `'label: loop { ... }`."),
+                Error::CannotCaptureEnvironment { variable, function } => Report::build(
+                    ReportKind::Error,
+                    variable.span.path,
+                    variable.span.range.start,
+                )
+                .with_message(format!(
+                    "cannot use {} inside of a nested function.",
+                    Color::Green.paint(variable.value)
+                ))
+                .with_label(
+                    cache.label(&variable.span)
+                        .with_message("This is a local of a surrounding function.")
+                        .with_color(Color::Red),
+                )
+                .with_label(
+                    cache.label(&function)
+                        .with_message("It's used inside of this function.")
+                        .with_color(Color::Blue),
+                )
+                .with_note(
+                    "Nested functions can't capture variables, pass it as a parameter instead.",
+                ),
+                Error::AssignToImmutable { name, declared_at } => {
+                    let report = Report::build(
+                        ReportKind::Error,
+                        name.span.path,
+                        name.span.range.start,
+                    )
+                    .with_message(format!(
+                        "cannot assign twice to the immutable variable {}.",
+                        Color::Green.paint(name.value)
+                    ))
+                    .with_label(
+                        cache.label(&name.span)
+                            .with_message("It's assigned to here.")
+                            .with_color(Color::Red),
+                    )
+                    .with_note(format!(
+                        "Declare the variable with {} to allow assigning to it.",
+                        Color::Green.paint("`var mut`")
+                    ));
+
+                    if let Some(span) = declared_at {
+                        report.with_label(
+                            cache.label(&span)
+                                .with_message("The variable is declared here.")
+                                .with_color(Color::Blue),
+                        )
+                    } else {
+                        report
+                    }
+                }
+                Error::OutOfRange { value, range } => {
+                    let report = Report::build(
+                        ReportKind::Error,
+                        value.span.path,
+                        value.span.range.start,
+                    )
+                    .with_message(format!(
+                        "{} is out of range for {}.",
+                        Color::Green.paint(value.value),
+                        Color::Cyan.paint(&range.value)
+                    ))
+                    .with_label(
+                        cache.label(&value.span)
+                            .with_message("This value is out of range.")
+                            .with_color(Color::Red),
+                    )
+                    .with_note("Both bounds of a ranged integer are inclusive.");
+
+                    if let Some(span) = range.span {
+                        report.with_label(
+                            cache.label(&span)
+                                .with_message(format!(
+                                    "It's used as a {} here.",
+                                    Color::Cyan.paint(&range.value)
+                                ))
+                                .with_color(Color::Blue),
+                        )
+                    } else {
+                        report
+                    }
+                }
+                Error::InvalidMainSignature(main) => Report::build(
+                    ReportKind::Error,
+                    main.span.path,
+                    main.span.range.start,
+                )
+                .with_message(format!(
+                    "The `main` function is of type {}.",
+                    Color::Cyan.paint(&main.value)
+                ))
+                .with_label(
+                    cache.label(&main.span)
+                        .with_message("This function is the entry point.")
+                        .with_color(Color::Red),
+                )
+                .with_note("The `main` function must take no parameters and return Unit."),
+                Error::ArgumentCountMismatch {
+                    expected,
+                    found,
+                    call_span,
+                    def_span,
+                } => Report::build(ReportKind::Error, call_span.path, call_span.range.start)
+                    .with_message(format!(
+                        "Expected {} but found {}.",
+                        Color::Cyan.paint(count_arguments(expected)),
+                        Color::Magenta.paint(count_arguments(found))
+                    ))
+                    .with_label(
+                        cache.label(&call_span)
+                            .with_message(format!(
+                                "This call passes {}.",
+                                Color::Magenta.paint(count_arguments(found))
+                            ))
+                            .with_color(Color::Magenta),
+                    )
+                    .with_label(
+                        cache.label(&def_span)
+                            .with_message(format!(
+                                "The function is defined here, taking {}.",
+                                Color::Cyan.paint(count_arguments(expected))
+                            ))
+                            .with_color(Color::Cyan),
+                    ),
                 Error::InvalidAssign(location) => Report::build(
                     ReportKind::Error,
                     location.span.path,
@@ -342,32 +712,48 @@ impl Display for Error {
                 )
                 .with_message(format!("Cannot assign to a {}.", location.value))
                 .with_label(
-                    Label::new((location.span.path, location.span.range))
-                        .with_message("Here.")
+                    cache.label(&location.span)
+                        .with_message(match cache.quote(&location.span) {
+                            Some(quote) => format!("{} can't be assigned to.", quote),
+                            None => String::from("Here."),
+                        })
                         .with_color(Color::Red),
                 )
                 .with_note("Only identifiers and fields can be assigned to."),
                 Error::MissingField {
                     structure,
                     field_name,
-                } => Report::build(
-                    ReportKind::Error,
-                    structure.span.path,
-                    structure.span.range.start,
-                )
-                .with_message(format!(
-                    "Field {} doesn't exist for {}",
-                    Color::Green.paint(field_name),
-                    Color::Green.paint(&structure.value)
-                ))
-                .with_label(
-                    Label::new((structure.span.path, structure.span.range))
-                        .with_message(format!(
-                            "You attempt to access {} here.",
-                            Color::Green.paint(field_name)
-                        ))
-                        .with_color(Color::Red),
-                ),
+                    instance,
+                } => {
+                    let report = Report::build(
+                        ReportKind::Error,
+                        structure.span.path,
+                        structure.span.range.start,
+                    )
+                    .with_message(format!(
+                        "Field {} doesn't exist for {}",
+                        Color::Green.paint(&field_name),
+                        Color::Green.paint(&structure.value)
+                    ))
+                    .with_label(
+                        cache.label(&structure.span)
+                            .with_message(format!(
+                                "You attempt to access {} here.",
+                                Color::Green.paint(&field_name)
+                            ))
+                            .with_color(Color::Red),
+                    );
+
+                    if let Some(span) = instance {
+                        report.with_label(
+                            cache.label(&span)
+                                .with_message("The instance was created here.")
+                                .with_color(Color::Cyan),
+                        )
+                    } else {
+                        report
+                    }
+                }
                 Error::InvalidFlow {
                     span,
                     construct: loop_flow,
@@ -377,7 +763,7 @@ impl Display for Error {
                         Color::Green.paint(loop_flow)
                     ))
                     .with_label(
-                        Label::new((span.path, span.range))
+                        cache.label(&span)
                             .with_message("Here.")
                             .with_color(Color::Red),
                     )
@@ -385,9 +771,54 @@ impl Display for Error {
                         "You cannot use a {} in the case of a nested function in a loop. ",
                         Color::Green.paint(loop_flow)
                     )),
+                Error::MissingFields { structure, missing } => Report::build(
+                    ReportKind::Error,
+                    structure.span.path,
+                    structure.span.range.start,
+                )
+                .with_message(format!(
+                    "the instance of {} is missing the {} {}.",
+                    Color::Green.paint(structure.value),
+                    if missing.len() > 1 { "fields" } else { "field" },
+                    missing
+                        .iter()
+                        .map(|field| Color::Green.paint(field).to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+                .with_label(
+                    cache.label(&structure.span)
+                        .with_message("Here.")
+                        .with_color(Color::Red),
+                )
+                .with_note("Every field of a structure must be given a value."),
+                Error::NonExhaustiveMatch { span, missing } => {
+                    Report::build(ReportKind::Error, span.path, span.range.start)
+                        .with_message(format!(
+                            "match doesn't handle the {} {}.",
+                            if missing.len() > 1 {
+                                "variants"
+                            } else {
+                                "variant"
+                            },
+                            missing
+                                .iter()
+                                .map(|variant| Color::Green.paint(variant).to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ))
+                        .with_label(
+                            cache.label(&span)
+                                .with_message("Here.")
+                                .with_color(Color::Red),
+                        )
+                        .with_note("Every variant of an enumeration must be handled by an arm.")
+                }
                 _ => unreachable!(),
             }
-            .with_config(ErrorConfig::default().with_compact(compact))
+            .with_origins(cache)
+            .with_code(code.unwrap())
+            .with_config(config)
             .finish()
             .eprint(cache)
             .unwrap(),
@@ -395,38 +826,122 @@ impl Display for Error {
     }
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum MessageFormat {
+    Human,
+    Json,
+}
+
 pub enum RunResult {
     Success,
     Failure,
 }
 
+// The `bell.toml` of a project, which gives defaults for what would otherwise be passed as flags on every compilation.
+// Flags that are passed anyway take priority over it.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    // The project or file to compile, relative to the manifest.
+    entry: Option<String>,
+    namespace: Option<String>,
+    // The Minecraft version the data pack targets, such as `1.19`.
+    target_version: Option<String>,
+    opt_level: Option<ManifestOptLevel>,
+    export: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ManifestOptLevel {
+    Debug,
+    Release,
+}
+
+fn read_manifest(path: &Path) -> Result<Manifest, String> {
+    let text = std::fs::read_to_string(path).map_err(|error| {
+        format!(
+            "failed to read the manifest `{}` because {}.",
+            path.display(),
+            generate_cause(error)
+        )
+    })?;
+
+    let manifest: Manifest = toml::from_str(&text)
+        .map_err(|error| format!("the manifest `{}` is malformed: {}.", path.display(), error))?;
+
+    // There is no backend to generate the data pack yet, so these would silently do nothing.
+    for (key, value) in [
+        ("namespace", &manifest.namespace),
+        ("target_version", &manifest.target_version),
+    ] {
+        if value.is_some() {
+            return Err(format!(
+                "the manifest `{}` sets `{}`, which isn't supported until data packs can be generated.",
+                path.display(),
+                key
+            ));
+        }
+    }
+
+    Ok(manifest)
+}
+
 struct Config {
-    #[allow(unused)]
     export_to: Option<String>,
     optimizations: OptLevel,
     path: String,
     compact_errors: bool,
+    // How many columns a tab is displayed as in errors, which should match the user's editor.
+    tab_width: usize,
+    message_format: MessageFormat,
+    stats: bool,
+    kind: ProjectKind,
+    warnings_as_errors: bool,
+    cache: Option<String>,
+    // The error code to explain, when using `--explain`.
+    explain: Option<String>,
+    list_builtins: bool,
+    // The file to format, when using the `fmt` subcommand.
+    format: Option<String>,
+    // The exported data pack to check, when using the `verify` subcommand.
+    verify: Option<String>,
+    dump_hir: bool,
+    dump_scopes: bool,
+    // Whether to print the graph of the project's modules instead of compiling it, when using `--emit module-graph`.
+    module_graph: bool,
 }
 
-fn get_config() -> Config {
+fn get_config() -> Result<Config, String> {
     let matches = App::new("The Bell CLI")
         .author("Yoav Grimland, miestrode@gmail.com")
-        .version("0.5.0")
+        .version(VERSION)
         .about("Compile a Bell project/file")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(
             Arg::new("path")
                 .short('p')
                 .long("path")
                 .value_name("FILE/FOLDER")
-                .about("Is used to specify the path of the project/file to compile")
-                .required(true)
+                .help("Is used to specify the path of the project/file to compile")
+                .required_unless_present_any(["explain", "list-builtins", "project-file"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("project-file")
+                .long("project-file")
+                .value_name("FILE")
+                .help(
+                    "Reads the path and defaults of the project from a manifest, such as `bell.toml`.\n Flags \
+                     that are passed anyway override it",
+                )
                 .takes_value(true),
         )
         .arg(
             Arg::new("optimizations")
                 .short('o')
                 .long("opt")
-                .about("Enables more complicated code optimizations")
+                .help("Enables more complicated code optimizations")
                 .takes_value(false),
         )
         .arg(
@@ -434,7 +949,7 @@ fn get_config() -> Config {
                 .short('e')
                 .long("export")
                 .value_name("FOLDER")
-                .about(
+                .help(
                     "Specifies what folder to put your compiled data pack in.\n When unused, it \
                      will print the data pack out",
                 )
@@ -446,35 +961,446 @@ fn get_config() -> Config {
                 .short('c')
                 .long("compact")
                 .takes_value(false)
-                .about("Makes error messages more compact."),
+                .help("Makes error messages more compact."),
+        )
+        .arg(
+            Arg::new("tab-width")
+                .long("tab-width")
+                .value_name("N")
+                .help("Specifies how many columns a tab takes up in error messages")
+                .validator(|width: &str| width.parse::<usize>())
+                .default_value("4")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("message-format")
+                .long("message-format")
+                .value_name("FORMAT")
+                .help("Specifies how errors are printed, either `human` or `json`")
+                .possible_values(["human", "json"])
+                .default_value("human")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("warnings-as-errors")
+                .short('W')
+                .long("warnings-as-errors")
+                .takes_value(false)
+                .help("Fails compilation if any warnings were produced"),
+        )
+        .arg(
+            Arg::new("library")
+                .long("lib")
+                .takes_value(false)
+                .help("Checks the project as a library, which doesn't need a `main` function"),
+        )
+        .arg(
+            Arg::new("stats")
+                .long("stats")
+                .takes_value(false)
+                .help("Prints how many names and paths were interned while compiling"),
+        )
+        .arg(
+            Arg::new("cache-dir")
+                .long("cache-dir")
+                .value_name("FOLDER")
+                .help("Caches parsed files in this folder, so unchanged files aren't parsed again")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("dump-hir")
+                .long("dump-hir")
+                .takes_value(false)
+                .help("Prints the HIR of the project instead of checking it"),
+        )
+        .arg(
+            Arg::new("dump-scopes")
+                .long("dump-scopes")
+                .takes_value(false)
+                .help("Prints the scopes of the project after it's items are gathered, instead of checking it"),
+        )
+        .arg(
+            Arg::new("emit")
+                .long("emit")
+                .value_name("KIND")
+                .help(
+                    "Emits something other than the data pack. `module-graph` is a Graphviz graph \
+                     of the project's modules and the imports between them",
+                )
+                .possible_values(["module-graph"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("list-builtins")
+                .long("list-builtins")
+                .takes_value(false)
+                .help("Prints the name and signature of every builtin function"),
+        )
+        .arg(
+            Arg::new("explain")
+                .long("explain")
+                .value_name("CODE")
+                .help("Prints a longer explanation of the error with this code, such as `E0007`")
+                .takes_value(true),
+        )
+        .subcommand(
+            App::new("fmt")
+                .about("Formats a Bell file, printing the result")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("Is used to specify the file to format")
+                        .required(true)
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("verify")
+                .about("Checks that an exported data pack is valid, and that every function it calls exists")
+                .arg(
+                    Arg::new("folder")
+                        .value_name("FOLDER")
+                        .help("Is used to specify the exported data pack to check")
+                        .required(true)
+                        .takes_value(true),
+                ),
         )
         .get_matches();
 
-    Config {
+    let manifest_path = matches.value_of("project-file").map(PathBuf::from);
+    let manifest = manifest_path.as_deref().map(read_manifest).transpose()?;
+
+    // Paths in the manifest are relative to the directory it is in, rather than to where the CLI is run.
+    let relative_to_manifest = |path: String| match manifest_path.as_deref().and_then(Path::parent)
+    {
+        Some(directory) => directory.join(path).to_string_lossy().into_owned(),
+        None => path,
+    };
+
+    let Manifest {
+        entry,
+        opt_level,
+        export,
+        ..
+    } = manifest.unwrap_or_default();
+
+    let path = matches
+        .value_of("path")
+        .map(|path| path.to_owned())
+        .or_else(|| entry.map(relative_to_manifest));
+
+    // Clap can only require the path if there is no manifest, which may still be missing it.
+    if path.is_none()
+        && !matches.is_present("explain")
+        && !matches.is_present("list-builtins")
+        && matches.subcommand().is_none()
+    {
+        return Err(String::from(
+            "no path to compile was given, pass `--path` or set `entry` in the manifest.",
+        ));
+    }
+
+    Ok(Config {
         optimizations: if matches.is_present("release") {
             OptLevel::Release
         } else {
-            OptLevel::Debug
+            match opt_level {
+                Some(ManifestOptLevel::Release) => OptLevel::Release,
+                Some(ManifestOptLevel::Debug) | None => OptLevel::Debug,
+            }
         },
-        export_to: matches.value_of("export").map(|path| path.to_owned()),
-        path: matches.value_of("path").unwrap().to_owned(),
+        export_to: matches
+            .value_of("export")
+            .map(|path| path.to_owned())
+            .or_else(|| export.map(relative_to_manifest)),
+        path: path.unwrap_or_default(),
         compact_errors: matches.is_present("compact"),
+        // The width was already validated, and it has a default value.
+        tab_width: matches.value_of("tab-width").unwrap().parse().unwrap(),
+        message_format: match matches.value_of("message-format") {
+            Some("json") => MessageFormat::Json,
+            _ => MessageFormat::Human,
+        },
+        stats: matches.is_present("stats"),
+        warnings_as_errors: matches.is_present("warnings-as-errors"),
+        kind: if matches.is_present("library") {
+            ProjectKind::Library
+        } else {
+            ProjectKind::Executable
+        },
+        cache: matches.value_of("cache-dir").map(|path| path.to_owned()),
+        explain: matches.value_of("explain").map(|code| code.to_owned()),
+        list_builtins: matches.is_present("list-builtins"),
+        format: matches
+            .subcommand_matches("fmt")
+            .map(|matches| matches.value_of("file").unwrap().to_owned()),
+        verify: matches
+            .subcommand_matches("verify")
+            .map(|matches| matches.value_of("folder").unwrap().to_owned()),
+        dump_hir: matches.is_present("dump-hir"),
+        dump_scopes: matches.is_present("dump-scopes"),
+        module_graph: matches.value_of("emit") == Some("module-graph"),
+    })
+}
+
+fn explain_error(code: &str) -> RunResult {
+    match explanations::explain(code) {
+        Some(explanation) => {
+            println!("{}", explanation);
+
+            RunResult::Success
+        }
+        None => {
+            display_basic_error(format!("{} is not a known error code.", code), None);
+
+            RunResult::Failure
+        }
+    }
+}
+
+fn format_file(file: &str, config: &Config) -> RunResult {
+    match lang::format(PathBuf::from(file)) {
+        Ok(formatted) => {
+            print!("{}", formatted);
+
+            RunResult::Success
+        }
+        Err(errors) => {
+            print_diagnostics(errors, config);
+
+            RunResult::Failure
+        }
     }
 }
 
+fn verify_pack(folder: &str) -> RunResult {
+    let problems = verify::verify(Path::new(folder));
+
+    if problems.is_empty() {
+        println!(
+            "{} {}",
+            Color::Green.paint("Verified").bold(),
+            Color::Blue.paint(folder)
+        );
+
+        RunResult::Success
+    } else {
+        for problem in problems {
+            display_basic_error(format!("{}.", problem), None);
+        }
+
+        RunResult::Failure
+    }
+}
+
+fn dump_hir(config: &Config) -> RunResult {
+    match lang::lower(
+        PathBuf::from(&config.path),
+        config.cache.as_ref().map(PathBuf::from),
+    ) {
+        Ok(module) => {
+            println!("{:#?}", module);
+
+            RunResult::Success
+        }
+        Err(errors) => {
+            print_diagnostics(errors, config);
+
+            RunResult::Failure
+        }
+    }
+}
+
+// The graph is put in the export folder as `modules.dot` when one is given, and printed otherwise.
+fn emit_module_graph(config: &Config) -> RunResult {
+    let graph = match lang::module_graph(
+        PathBuf::from(&config.path),
+        config.cache.as_ref().map(PathBuf::from),
+    ) {
+        Ok(graph) => graph,
+        Err(errors) => {
+            print_diagnostics(errors, config);
+
+            return RunResult::Failure;
+        }
+    };
+
+    match &config.export_to {
+        Some(folder) => match std::fs::write(PathBuf::from(folder).join("modules.dot"), graph) {
+            Ok(()) => RunResult::Success,
+            Err(error) => {
+                display_basic_error(
+                    format!(
+                        "failed to write the module graph to `{}` because {}.",
+                        folder,
+                        generate_cause(error)
+                    ),
+                    None,
+                );
+
+                RunResult::Failure
+            }
+        },
+        None => {
+            print!("{}", graph);
+
+            RunResult::Success
+        }
+    }
+}
+
+fn dump_scopes(config: &Config) -> RunResult {
+    match lang::dump_scopes(
+        PathBuf::from(&config.path),
+        config.cache.as_ref().map(PathBuf::from),
+    ) {
+        Ok(scopes) => {
+            print!("{}", scopes);
+
+            RunResult::Success
+        }
+        Err(errors) => {
+            print_diagnostics(errors, config);
+
+            RunResult::Failure
+        }
+    }
+}
+
+// A panic is a bug in the compiler rather than in the program being compiled, so it's reported as one, along with what
+// the compiler was doing when it happened.
+fn install_panic_hook() {
+    panic::set_hook(Box::new(|info| {
+        eprintln!(
+            "{} {}",
+            Color::Red.paint("Internal compiler error (ICE):"),
+            info
+        );
+
+        if let Some(Progress { phase, path, span }) = progress::current() {
+            match (path, span) {
+                (_, Some(span)) => {
+                    // The sources aren't available here, so the file is read again to find the line.
+                    let position = std::fs::read_to_string(span.path.as_std_path())
+                        .ok()
+                        .and_then(|text| {
+                            let before = text.get(..span.range.start)?;
+
+                            Some(format!(
+                                "{}:{}",
+                                before.matches('\n').count() + 1,
+                                before.chars().rev().take_while(|&c| c != '\n').count() + 1
+                            ))
+                        })
+                        .unwrap_or_else(|| format!("byte {}", span.range.start));
+
+                    eprintln!(
+                        "This happened while {} `{}` at {}.",
+                        phase, span.path, position
+                    )
+                }
+                (Some(path), None) => eprintln!("This happened while {} `{}`.", phase, path),
+                (None, None) => eprintln!("This happened while {}.", phase),
+            }
+        }
+
+        eprintln!(
+            "The compiler is version {}, of language version {}.",
+            VERSION, LANGUAGE_VERSION
+        );
+        eprintln!("\n{}", Backtrace::force_capture());
+        eprintln!(
+            "{}",
+            Color::RGB(128, 128, 128)
+                .paint("This is a bug in the compiler, please report it along with the code that caused it.")
+        );
+    }));
+}
+
 pub fn run() -> RunResult {
-    let config = get_config();
-    println!(
-        "{} {}\n",
-        Color::Green.paint("Compiling").bold(),
-        Color::Blue.paint(&config.path)
-    );
+    install_panic_hook();
+
+    let config = match get_config() {
+        Ok(config) => config,
+        Err(message) => {
+            display_basic_error(message, None);
+
+            return RunResult::Failure;
+        }
+    };
+
+    if let Some(code) = &config.explain {
+        return explain_error(code);
+    }
+
+    if config.list_builtins {
+        for (name, signature) in builtin::builtins() {
+            println!("{}: {}", name, signature);
+        }
+
+        return RunResult::Success;
+    }
+
+    if let Some(file) = &config.format {
+        return format_file(file, &config);
+    }
+
+    if let Some(folder) = &config.verify {
+        return verify_pack(folder);
+    }
+
+    if config.dump_hir {
+        return dump_hir(&config);
+    }
+
+    if config.dump_scopes {
+        return dump_scopes(&config);
+    }
+
+    if config.module_graph {
+        return emit_module_graph(&config);
+    }
+
+    // When printing JSON the output is meant for other tools, so it must only contain the diagnostics.
+    let human = config.message_format == MessageFormat::Human;
+
+    if human {
+        println!(
+            "{} {}\n",
+            Color::Green.paint("Compiling").bold(),
+            Color::Blue.paint(&config.path)
+        );
+    }
 
     let path = PathBuf::from(&config.path);
     let time = Instant::now();
 
-    match lang::compile(path, config.optimizations) {
-        Ok(_) => {
+    let (result, stats) = lang::compile(
+        path,
+        config.optimizations,
+        config.kind,
+        config.cache.as_ref().map(PathBuf::from),
+    );
+
+    if config.stats && human {
+        println!(
+            "{} {} names and {} paths\n",
+            Color::Green.paint("Interned").bold(),
+            stats.interned_names,
+            stats.interned_paths
+        );
+    }
+
+    let (compiled, diagnostics) = match result {
+        Ok((_, warnings)) => (true, warnings),
+        Err(errors) => (false, errors),
+    };
+    let denied_warnings = config.warnings_as_errors && !diagnostics.warnings.is_empty();
+
+    print_diagnostics(diagnostics, &config);
+
+    if compiled && !denied_warnings {
+        if human {
             println!("{}", Color::RGB(128, 128, 128).paint("No output :)"));
 
             let elapsed = time.elapsed().as_secs_f32();
@@ -488,23 +1414,56 @@ pub fn run() -> RunResult {
                 }),
                 elapsed
             );
+        }
 
-            RunResult::Success
+        RunResult::Success
+    } else {
+        if human {
+            println!(
+                "{} compilation due to the {} above.",
+                Color::Red.paint("Failed").bold(),
+                if compiled { "warnings" } else { "errors" }
+            );
         }
-        Err(Errors { errors, sources }) => {
+
+        RunResult::Failure
+    }
+}
+
+fn print_diagnostics(mut errors: Errors, config: &Config) {
+    errors.sort_by_span();
+
+    let Errors {
+        errors,
+        warnings,
+        sources,
+    } = errors;
+
+    match config.message_format {
+        MessageFormat::Human => {
             let mut cache = ErrorSources::from(sources);
+            let error_config = ErrorConfig::default()
+                .with_compact(config.compact_errors)
+                .with_tab_width(config.tab_width);
 
-            for error in errors {
-                error.display(&mut cache, config.compact_errors);
+            for warning in warnings {
+                warning.display(&mut cache, error_config);
                 println!();
             }
 
-            println!(
-                "{} compilation due to the errors above.",
-                Color::Red.paint("Failed").bold()
-            );
-
-            RunResult::Failure
+            for error in errors {
+                error.display(&mut cache, error_config);
+                println!();
+            }
+        }
+        MessageFormat::Json => {
+            for diagnostic in warnings
+                .into_iter()
+                .map(Diagnostic::from)
+                .chain(errors.into_iter().map(Diagnostic::from))
+            {
+                println!("{}", serde_json::to_string(&diagnostic).unwrap());
+            }
         }
     }
 }
@@ -4,7 +4,7 @@ use std::process;
 
 fn main() {
     match cli::run() {
-        RunResult::Success => process::exi:t(0),
+        RunResult::Success => process::exit(0),
         RunResult::Failure => process::exit(1),
     }
 }
@@ -0,0 +1,274 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Checks that an exported data pack is one Minecraft would load, and that every function it calls exists. The problems
+// are returned as messages, so that all of them can be reported at once.
+pub fn verify(folder: &Path) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    verify_metadata(folder, &mut problems);
+
+    let mut functions = Vec::new();
+    let data = folder.join("data");
+
+    match fs::read_dir(&data) {
+        Ok(namespaces) => {
+            for namespace in namespaces.flatten() {
+                let name = namespace.file_name().to_string_lossy().into_owned();
+                let root = namespace.path().join("functions");
+
+                if root.is_dir() {
+                    collect_functions(
+                        &name,
+                        &root,
+                        &root,
+                        &mut Vec::new(),
+                        &mut functions,
+                        &mut problems,
+                    );
+                }
+            }
+        }
+        Err(error) => problems.push(format!(
+            "failed to read `{}` because {}",
+            data.display(),
+            error
+        )),
+    }
+
+    let defined = functions
+        .iter()
+        .map(|(id, _)| id.as_str())
+        .collect::<HashSet<_>>();
+
+    for (id, path) in &functions {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) => {
+                problems.push(format!(
+                    "failed to read the function `{}` because {}",
+                    id, error
+                ));
+
+                continue;
+            }
+        };
+
+        for (line, command) in text.lines().enumerate() {
+            for reference in referenced_functions(command) {
+                if !defined.contains(reference.as_str()) {
+                    problems.push(format!(
+                        "`{}`:{} calls the function `{}`, which doesn't exist",
+                        path.display(),
+                        line + 1,
+                        reference
+                    ));
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+fn verify_metadata(folder: &Path, problems: &mut Vec<String>) {
+    let path = folder.join("pack.mcmeta");
+
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(error) => {
+            return problems.push(format!(
+                "failed to read `{}` because {}",
+                path.display(),
+                error
+            ))
+        }
+    };
+
+    match serde_json::from_str::<serde_json::Value>(&text) {
+        Ok(metadata) => {
+            if !metadata["pack"]["pack_format"].is_u64() {
+                problems.push(format!(
+                    "`{}` doesn't give the pack format as a number at `pack.pack_format`",
+                    path.display()
+                ));
+            }
+        }
+        Err(error) => problems.push(format!("`{}` isn't valid JSON: {}", path.display(), error)),
+    }
+}
+
+// A function's id is it's namespace followed by it's path inside of the `functions` directory, such as `project:_3`. The
+// surrounding directories are kept, since a symbolic link to one of them would otherwise be followed forever.
+fn collect_functions(
+    namespace: &str,
+    root: &Path,
+    directory: &Path,
+    surrounding: &mut Vec<PathBuf>,
+    functions: &mut Vec<(String, PathBuf)>,
+    problems: &mut Vec<String>,
+) {
+    let canonical = match directory.canonicalize() {
+        Ok(canonical) => canonical,
+        Err(error) => {
+            return problems.push(format!(
+                "failed to read `{}` because {}",
+                directory.display(),
+                error
+            ))
+        }
+    };
+
+    if surrounding.contains(&canonical) {
+        return problems.push(format!(
+            "`{}` links to `{}`, which surrounds it",
+            directory.display(),
+            canonical.display()
+        ));
+    }
+
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(error) => {
+            return problems.push(format!(
+                "failed to read `{}` because {}",
+                directory.display(),
+                error
+            ))
+        }
+    };
+
+    surrounding.push(canonical);
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_functions(namespace, root, &path, surrounding, functions, problems);
+        } else if path.extension() == Some("mcfunction".as_ref()) {
+            let id = path
+                .strip_prefix(root)
+                .unwrap()
+                .with_extension("")
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            functions.push((format!("{}:{}", namespace, id), path));
+        }
+    }
+
+    surrounding.pop();
+}
+
+// Commands whose arguments end with text that is shown to players, which may mention a function without calling it.
+const TEXT_COMMANDS: [&str; 8] = ["say", "tellraw", "me", "msg", "tell", "w", "teammsg", "tm"];
+
+// Functions can be called directly or at the end of an `execute`, so a `function` where a command starts is followed by
+// the id of the called function. A command starts at the beginning of the line and after each `run`. Comments and calls
+// to function tags, which start with `#`, aren't checked.
+fn referenced_functions(command: &str) -> Vec<String> {
+    let command = command.trim();
+
+    if command.starts_with('#') {
+        return Vec::new();
+    }
+
+    let mut references = Vec::new();
+    let mut words = command.split_whitespace();
+    let mut starts_command = true;
+
+    while let Some(word) = words.next() {
+        if starts_command {
+            if TEXT_COMMANDS.contains(&word) {
+                break;
+            }
+
+            if word == "function" {
+                match words.next() {
+                    Some(id) if id.starts_with('#') => {}
+                    Some(id) if id.contains(':') => references.push(id.to_owned()),
+                    Some(id) => references.push(format!("minecraft:{}", id)),
+                    None => {}
+                }
+            }
+        }
+
+        starts_command = word == "run";
+    }
+
+    references
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{referenced_functions, verify};
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn packs_calling_only_their_own_functions_are_valid() {
+        let problems = verify(&fixture("valid"));
+
+        assert!(problems.is_empty(), "{:?}", problems);
+    }
+
+    #[test]
+    fn calls_to_missing_functions_are_reported() {
+        let problems = verify(&fixture("missing_function"));
+
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(
+            problems[0].ends_with(":2 calls the function `project:missing`, which doesn't exist")
+        );
+    }
+
+    #[test]
+    fn text_shown_to_players_calls_nothing() {
+        assert!(referenced_functions("say function project:missing").is_empty());
+        assert!(
+            referenced_functions("execute as @a run tellraw @s \"function project:missing\"")
+                .is_empty()
+        );
+        assert_eq!(
+            referenced_functions("execute as @a run function helper"),
+            ["minecraft:helper"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_cycles_are_reported() {
+        use std::fs;
+        use std::os::unix::fs::symlink;
+
+        let root = std::env::temp_dir().join(format!("bell-verify-cycle-{}", std::process::id()));
+        let functions = root.join("data").join("project").join("functions");
+        let _ = fs::remove_dir_all(&root);
+
+        fs::create_dir_all(functions.join("inner")).unwrap();
+        fs::copy(
+            fixture("valid").join("pack.mcmeta"),
+            root.join("pack.mcmeta"),
+        )
+        .unwrap();
+        fs::write(functions.join("main.mcfunction"), "say hello\n").unwrap();
+        symlink(&functions, functions.join("inner").join("loop")).unwrap();
+
+        let problems = verify(&root);
+
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(problems[0].contains("which surrounds it"));
+    }
+}
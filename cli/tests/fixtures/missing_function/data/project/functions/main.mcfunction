@@ -0,0 +1,2 @@
+function project:main
+execute if entity @a run function project:missing
@@ -0,0 +1,6 @@
+# Calls through `execute` and to tags are both fine.
+function project:util/helper
+execute as @a at @s run function project:util/helper
+function #minecraft:tick
+say the function project:missing is only text
+tellraw @a {"text":"function project:missing"}
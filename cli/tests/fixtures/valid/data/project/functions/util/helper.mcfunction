@@ -0,0 +1 @@
+scoreboard players add @s project 1
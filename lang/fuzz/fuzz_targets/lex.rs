@@ -0,0 +1,22 @@
+#![no_main]
+
+use camino::Utf8PathBuf;
+use internment::Intern;
+use libfuzzer_sys::fuzz_target;
+
+use lang::core::{error::Errors, span::SourceMap};
+use lang::front_end::{lex, parse};
+
+// Lexing and parsing must report errors for any input instead of panicking. The tokens are parsed too, since the parser
+// is what flattens the nested tokens the lexer produces.
+fuzz_target!(|text: &str| {
+    let path = Intern::new(Utf8PathBuf::from("fuzz.bell"));
+    let mut errors = Errors {
+        errors: Vec::new(),
+        warnings: Vec::new(),
+        sources: SourceMap::new(),
+    };
+
+    let tokens = lex::lex(path, text, &mut errors);
+    parse::parse(path, tokens, &mut errors);
+});
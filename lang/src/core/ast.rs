@@ -1,22 +1,81 @@
 use internment::Intern;
+use serde::{Deserialize, Serialize};
+use std::error::Error as StdError;
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::ops::Deref;
+use std::str::FromStr;
 
 use crate::core::span::Span;
 
 use super::Name;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Id(pub Vec<Intern<String>>);
 
 impl Id {
+    // Every ID names something, so it has at least one part.
     pub fn new(id: Vec<Intern<String>>) -> Self {
+        assert!(!id.is_empty(), "an ID must have at least one part");
+
         Id(id)
     }
 
     pub fn new_single(id: Intern<String>) -> Self {
         Id(vec![id])
     }
+
+    // The first part of the ID, which is searched for in the surrounding modules.
+    pub fn root(&self) -> Name {
+        self.0[0]
+    }
+
+    // The modules the last part of the ID is in. Every ID has at least one part, so these are all but the last one.
+    pub fn parent(&self) -> &[Name] {
+        &self.0[..self.0.len() - 1]
+    }
+
+    // The item the ID names, inside of it's parent modules.
+    pub fn leaf(&self) -> Name {
+        *self.0.last().unwrap()
+    }
+
+    // The name of an ID that isn't inside of any module, such as a local variable or a builtin.
+    pub fn as_single(&self) -> Option<Name> {
+        match self.0.as_slice() {
+            [name] => Some(*name),
+            _ => None,
+        }
+    }
+}
+
+// An ID that was parsed had an empty part, such as in `a::`, `::b` or an empty string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseIdError;
+
+impl Display for ParseIdError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("an ID can't have an empty part")
+    }
+}
+
+impl StdError for ParseIdError {}
+
+// Parses the way IDs are displayed, so that any ID whose parts don't contain `::` is parsed back into itself.
+impl FromStr for Id {
+    type Err = ParseIdError;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        id.split("::")
+            .map(|part| {
+                if part.is_empty() {
+                    Err(ParseIdError)
+                } else {
+                    Ok(Intern::new(part.to_string()))
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(Id)
+    }
 }
 
 impl Display for Id {
@@ -39,6 +98,16 @@ impl From<Intern<String>> for Id {
     }
 }
 
+// Closures don't have a name of their own, so they are given one that no identifier could be spelled as. It includes
+// where the closure starts, so no two closures in a file share a name either.
+pub fn closure_name(span: &Span) -> Name {
+    Intern::new(format!("<closure@{}>", span.range.start))
+}
+
+pub fn is_closure_name(name: &str) -> bool {
+    name.starts_with("<closure@")
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum FlowKind {
     Return,
@@ -46,16 +115,52 @@ pub enum FlowKind {
     Break,
 }
 
-#[derive(Debug, Clone)]
+// The names type hints always take as primitive types, rather than as structures.
+pub const PRIMITIVE_TYPES: &[&str] = &["Int", "I8", "I16", "I64", "Bool", "Str"];
+
+// The width of an integer with an explicit size. Values of different widths are never unified, they must be converted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntegerWidth {
+    I8,
+    I16,
+    I64,
+}
+
+impl IntegerWidth {
+    // Constants can't be larger than an `Int`, so the range of a wider integer can't be represented.
+    pub fn range(self) -> Option<(i32, i32)> {
+        match self {
+            IntegerWidth::I8 => Some((i8::MIN.into(), i8::MAX.into())),
+            IntegerWidth::I16 => Some((i16::MIN.into(), i16::MAX.into())),
+            IntegerWidth::I64 => None,
+        }
+    }
+}
+
+impl Display for IntegerWidth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            IntegerWidth::I8 => write!(f, "I8"),
+            IntegerWidth::I16 => write!(f, "I16"),
+            IntegerWidth::I64 => write!(f, "I64"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Type {
     Integer,
+    // An integer which is statically known to be between the bounds, including them.
+    RangedInteger { min: i32, max: i32 },
+    SizedInteger(IntegerWidth),
     Boolean,
     String,
     Structure(Id),
     Reference(Box<Type>),
+    Optional(Box<Type>),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypeHint<T> {
     pub value: T,
     pub type_hint: Option<(Type, Span)>,
@@ -77,40 +182,50 @@ impl<T> TypeHint<T> {
 }
 
 type Field = ((Name, Span), (Expression, Span));
+type Variant = ((Name, Span), Vec<(Type, Span)>);
+type Arm = ((Id, Span), Vec<(Name, Span)>, (Expression, Span));
 
-struct Path(Vec<Id>);
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Expression {
+    Unit,
+    Null,
     Int(i32),
     Boolean(bool),
-    String(Id),
+    String(Name),
     Identifier(Id),
     Function {
         name: TypeHint<(Name, Span)>,
         parameters: Vec<TypeHint<(Name, Span)>>,
         body: Box<(Expression, Span)>,
     },
+    // An instance may be based on another one, which gives it any field that isn't given explicitly.
     Instance {
         object: (Id, Span),
         fields: Vec<Field>,
+        base: Option<Box<(Expression, Span)>>,
     },
     Call {
         function: Box<(Expression, Span)>,
         parameters: (Vec<(Expression, Span)>, Span),
     },
+    // Variables can only be assigned to after their declaration if they are declared with `var mut`.
     Declaration {
         name: TypeHint<(Name, Span)>,
         value: Box<(Expression, Span)>,
+        mutable: bool,
     },
     Assignment {
         to: Box<(Expression, Span)>,
         from: Box<(Expression, Span)>,
+        // The operator of a compound assignment, such as `+=`.
+        operator: Option<(Id, Span)>,
     },
     Access {
         from: Box<(Expression, Span)>,
         field: (Name, Span),
     },
+    Reference(Box<(Expression, Span)>),
+    Dereference(Box<(Expression, Span)>),
     Block {
         expressions: Vec<(Expression, Span)>,
         tail: Option<Box<(Expression, Span)>>,
@@ -119,15 +234,39 @@ pub enum Expression {
         name: (Name, Span),
         fields: Vec<TypeHint<(Name, Span)>>,
     },
+    Enumeration {
+        name: (Name, Span),
+        variants: Vec<Variant>,
+    },
     Conditional {
         branches: Vec<((Expression, Span), (Expression, Span))>,
         tail: Option<Box<(Expression, Span)>>,
     },
-    Break(Box<(Expression, Span)>),
+    Match {
+        scrutinee: Box<(Expression, Span)>,
+        arms: Vec<Arm>,
+    },
+    Break {
+        label: Option<(Name, Span)>,
+        value: Box<(Expression, Span)>,
+    },
     Return(Box<(Expression, Span)>),
-    Import((Id, Span)),
-    Loop(Box<(Expression, Span)>),
-    Continue,
+    Import {
+        path: (Id, Span),
+        // The name the item is imported as, if it's renamed, such as in `use shapes::circle as round`.
+        alias: Option<(Name, Span)>,
+    },
+    Loop {
+        label: Option<(Name, Span)>,
+        body: Box<(Expression, Span)>,
+    },
+    Continue(Option<(Name, Span)>),
+    // `@typeof(value)`, which is the inferred type of the value as a string.
+    TypeOf(Box<(Expression, Span)>),
+    // `@assert(condition)`, which fails compilation if the condition is known to be false.
+    Assert(Box<(Expression, Span)>),
+    // `@dbg(value)`, which prints the value and then evaluates to it unchanged.
+    Dbg(Box<(Expression, Span)>),
     Error,
 }
 
@@ -138,3 +277,34 @@ pub enum Module {
     Submodule { name: Name, modules: Vec<Module> },
     Program { name: Name, ast: Program },
 }
+
+#[cfg(test)]
+mod tests {
+    use internment::Intern;
+
+    use super::{Id, ParseIdError};
+
+    #[test]
+    fn ids_round_trip_through_display() {
+        for text in ["a", "a::b", "outer::inner::item"] {
+            let id = text.parse::<Id>().unwrap();
+
+            assert_eq!(id.to_string(), text);
+            assert_eq!(id.to_string().parse::<Id>(), Ok(id));
+        }
+
+        let id = Id::new(vec![
+            Intern::new("a".to_string()),
+            Intern::new("b".to_string()),
+        ]);
+
+        assert_eq!(id.to_string().parse::<Id>(), Ok(id));
+    }
+
+    #[test]
+    fn ids_with_empty_parts_are_rejected() {
+        for text in ["", "a::", "::b", "a::::b"] {
+            assert_eq!(text.parse::<Id>(), Err(ParseIdError));
+        }
+    }
+}
@@ -0,0 +1,482 @@
+use std::io;
+use std::iter;
+
+use serde::Serialize;
+
+use super::error::{Error, Pattern, Reason, Warning};
+use super::span::Span;
+use super::types::LinkReason;
+
+// These are shared with the human readable output of errors, so that the two always say the same thing.
+pub fn display_as_choice(list: &[Pattern]) -> String {
+    format!(
+        "{}{}",
+        list[..list.len() - 1]
+            .iter()
+            .map(|pattern| pattern.to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+        if list.len() > 2 {
+            format!(", or {}", list.last().unwrap())
+        } else if list.len() == 2 {
+            format!(" or {}", list.last().unwrap())
+        } else {
+            list.first().unwrap().to_string()
+        }
+    )
+}
+
+pub fn count_arguments(count: usize) -> String {
+    format!("{} argument{}", count, if count == 1 { "" } else { "s" })
+}
+
+pub fn generate_cause(error: io::Error) -> String {
+    format!(
+        "{} (error no. {})",
+        match error.kind() {
+            io::ErrorKind::NotFound => "it was not found",
+            io::ErrorKind::PermissionDenied => "permission was denied",
+            io::ErrorKind::Interrupted => "the operation was interrupted",
+            _ => unreachable!(),
+        },
+        error
+            .raw_os_error()
+            .map_or(String::from("?"), |os_error| os_error.to_string())
+    )
+}
+
+pub fn link_reason_note(reason: LinkReason) -> Option<&'static str> {
+    Some(match reason {
+        LinkReason::Other => return None,
+        LinkReason::Assign => {
+            "Assignments cannot change the type of a memory location. You must shadow it in order \
+             to do that."
+        }
+        LinkReason::Condition => "This condition needs to return a boolean.",
+        LinkReason::Conditional => {
+            "All branches in a conditional expression must return the same type."
+        }
+        LinkReason::Match => "All arms of a match expression must return the same type.",
+        LinkReason::Pattern => "A pattern must match the type of the value being matched.",
+        LinkReason::Binding => "Only an optional value can be bound by a conditional.",
+        LinkReason::Dereference => "Only a reference can be dereferenced.",
+        LinkReason::Field => "A field must be assigned a value of it's compatible type.",
+        LinkReason::Loop => "Every break from a loop must be of the same type.",
+        LinkReason::Parameter => "A function must be called with arguments of matching types.",
+        LinkReason::Return => "All returns from a function must be of the same type.",
+        LinkReason::Structure => {
+            "You must fill out all fields of a structure when constructing it."
+        }
+        LinkReason::Call => {
+            "When calling a function, you must specify the values for it's exact number of \
+             arguments."
+        }
+        LinkReason::Declaration => {
+            "The type hint of this declaration doesn't match the value of it."
+        }
+    })
+}
+
+#[derive(Serialize)]
+pub struct DiagnosticSpan {
+    pub(crate) path: String,
+    // These are the exact byte offsets of the span in the source file.
+    start: usize,
+    end: usize,
+}
+
+impl From<&Span> for DiagnosticSpan {
+    fn from(span: &Span) -> Self {
+        Self {
+            path: span.path.to_string(),
+            start: span.range.start,
+            end: span.range.end,
+        }
+    }
+}
+
+// A plain representation of an error, used when printing errors as JSON for other tools to consume.
+#[derive(Serialize)]
+pub struct Diagnostic {
+    // Either `error` or `warning`.
+    level: &'static str,
+    kind: &'static str,
+    code: Option<&'static str>,
+    message: String,
+    spans: Vec<DiagnosticSpan>,
+    notes: Vec<String>,
+}
+
+impl Diagnostic {
+    fn new(kind: &'static str, message: String, spans: Vec<&Span>, notes: Vec<String>) -> Self {
+        Self {
+            level: "error",
+            kind,
+            code: None,
+            message,
+            spans: spans.into_iter().map(DiagnosticSpan::from).collect(),
+            notes,
+        }
+    }
+}
+
+impl From<Warning> for Diagnostic {
+    fn from(warning: Warning) -> Self {
+        let code = warning.code();
+
+        let diagnostic = match warning {
+            Warning::ShadowsFunction {
+                name,
+                original,
+                shadow,
+            } => Self::new(
+                "shadows_function",
+                format!("the variable {} shadows a function", name),
+                vec![&shadow, &original],
+                vec![String::from(
+                    "Rename the variable if the function is still needed.",
+                )],
+            ),
+            Warning::ConstantCondition { span, value } => Self::new(
+                "constant_condition",
+                format!("this condition is always {}", value),
+                vec![&span],
+                vec![format!(
+                    "Only the {} branch is ever taken.",
+                    if value { "first" } else { "second" }
+                )],
+            ),
+            Warning::TypeQuery { span, data_type } => Self::new(
+                "type_query",
+                format!("this value is of type {}", data_type),
+                vec![&span],
+                vec![String::from("Remove the `@typeof` once it isn't needed.")],
+            ),
+            Warning::UnreachableBranch { span } => Self::new(
+                "unreachable_branch",
+                String::from("this branch is unreachable"),
+                vec![&span],
+                vec![String::from(
+                    "The condition before it is always true, so remove the branch or fix the condition.",
+                )],
+            ),
+        };
+
+        Self {
+            level: "warning",
+            code: Some(code),
+            ..diagnostic
+        }
+    }
+}
+
+impl From<Error> for Diagnostic {
+    fn from(error: Error) -> Self {
+        let code = error.code();
+
+        let diagnostic = match error {
+            Error::Basic(message) => Self::new("basic", message, Vec::new(), Vec::new()),
+            Error::BasicAt { message, span } => {
+                Self::new("basic", message, vec![&span], Vec::new())
+            }
+            Error::IO { error, action } => Self::new(
+                "io",
+                format!("failed to {} because {}", action, generate_cause(error)),
+                Vec::new(),
+                Vec::new(),
+            ),
+            Error::UnterminatedBlockComment { span } => Self::new(
+                "unterminated_block_comment",
+                String::from("unterminated block comment"),
+                vec![&span],
+                vec![String::from("A block comment termination looks like `*/`.")],
+            ),
+            Error::UnterminatedString { span } => Self::new(
+                "unterminated_string",
+                String::from("unterminated string"),
+                vec![&span],
+                vec![String::from("A string termination looks like `\"`.")],
+            ),
+            Error::ConstantOverflow { span } => Self::new(
+                "constant_overflow",
+                String::from("this arithmetic overflows"),
+                vec![&span],
+                vec![String::from(
+                    "Integers range from -2147483648 to 2147483647, inclusive.",
+                )],
+            ),
+            Error::ReservedTypeName { name, span } => Self::new(
+                "reserved_type_name",
+                format!(
+                    "{} is the name of a type, so it can't name a variable",
+                    name
+                ),
+                vec![&span],
+                vec![String::from(
+                    "Type hints would still refer to the type, so give the variable another name.",
+                )],
+            ),
+            Error::InfiniteType { span } => Self::new(
+                "infinite_type",
+                String::from("this type would contain itself"),
+                vec![&span],
+                vec![String::from(
+                    "A value can't be used as part of itself, give it a type explicitly to find the mistake.",
+                )],
+            ),
+            Error::MissingFunctionBody { function_span } => Self::new(
+                "missing_function_body",
+                String::from("this function has no body"),
+                vec![&function_span],
+                vec![String::from("Add a body after the function, such as `{}`.")],
+            ),
+            Error::Unsupported { construct, span } => Self::new(
+                "unsupported",
+                format!("{} isn't supported yet", construct),
+                vec![&span],
+                vec![String::from(
+                    "The compiler can't generate code for this yet, so it must be written another way.",
+                )],
+            ),
+            Error::ChainedComparison { span } => Self::new(
+                "chained_comparison",
+                String::from("comparison operators cannot be chained"),
+                vec![&span],
+                vec![String::from(
+                    "Compare each pair on it's own, such as `a < b && b < c`.",
+                )],
+            ),
+            Error::AssertionFailed { span } => Self::new(
+                "assertion_failed",
+                String::from("assertion is always false"),
+                vec![&span],
+                Vec::new(),
+            ),
+            Error::Unexpected {
+                expected,
+                found,
+                while_parsing,
+                reason,
+            } => {
+                let message = format!(
+                    "expected {} but found {}",
+                    display_as_choice(&expected.into_iter().collect::<Vec<_>>()),
+                    found.value
+                );
+                let mut spans = vec![&found.span];
+
+                if let Reason::UnclosedDelimiter(delimiter) = &reason {
+                    spans.push(&delimiter.span);
+                }
+
+                Self::new(
+                    "unexpected",
+                    if let Some(while_parsing) = while_parsing {
+                        format!("{} while parsing {}", message, while_parsing)
+                    } else {
+                        message
+                    },
+                    spans,
+                    Vec::new(),
+                )
+            }
+            Error::ConflictingModuleNames {
+                parent,
+                name,
+                first,
+                second,
+            } => Self::new(
+                "conflicting_module_names",
+                format!(
+                    "the child module {} exists more than once in the parent module {}",
+                    name, parent
+                ),
+                Vec::new(),
+                vec![
+                    format!("the first module is at {}", first),
+                    format!("the second module is at {}", second),
+                ],
+            ),
+            Error::ArgumentCountMismatch {
+                expected,
+                found,
+                call_span,
+                def_span,
+            } => Self::new(
+                "argument_count_mismatch",
+                format!(
+                    "expected {} but found {}",
+                    count_arguments(expected),
+                    count_arguments(found)
+                ),
+                vec![&call_span, &def_span],
+                Vec::new(),
+            ),
+            Error::MissingMain => Self::new(
+                "missing_main",
+                String::from("the project has no `main` function in it's entry module"),
+                Vec::new(),
+                vec![String::from(
+                    "Use `--lib` if the project isn't meant to be executed.",
+                )],
+            ),
+            Error::InvalidMainSignature(main) => Self::new(
+                "invalid_main_signature",
+                format!("the `main` function is of type {}", main.value),
+                vec![&main.span],
+                vec![String::from(
+                    "The `main` function must take no parameters and return Unit.",
+                )],
+            ),
+            Error::ModuleCycle { path, target } => Self::new(
+                "module_cycle",
+                format!(
+                    "the directory {} is a link to {}, which contains it",
+                    path, target
+                ),
+                Vec::new(),
+                Vec::new(),
+            ),
+            Error::MissingEntryPoint { expected } => Self::new(
+                "missing_entry_point",
+                format!(
+                    "the project has no entry point, expected it at {}",
+                    expected
+                ),
+                Vec::new(),
+                vec![
+                    "a project is a directory whose root module is a `main.bell` file".to_string(),
+                ],
+            ),
+            Error::InvalidAssign(location) => Self::new(
+                "invalid_assign",
+                format!("cannot assign to a {}", location.value),
+                vec![&location.span],
+                vec![String::from(
+                    "Only identifiers and fields can be assigned to.",
+                )],
+            ),
+            Error::MissingId { id } => Self::new(
+                "missing_id",
+                format!("cannot find {} in scope", id.value),
+                vec![&id.span],
+                Vec::new(),
+            ),
+            Error::ConflictingIds { first, second, id } => Self::new(
+                "conflicting_ids",
+                format!("the name {} is conflicted between two identifiers", id),
+                vec![&first, &second],
+                vec![String::from(
+                    "To avoid ambiguities, non-variable identifiers must be unique in their scope.",
+                )],
+            ),
+            Error::TypeMismatch { a, b, reason } => Self::new(
+                "type_mismatch",
+                format!(
+                    "type mismatch between {} and {}",
+                    a.0[0].data_type.value, b.0[0].data_type.value
+                ),
+                a.0.iter()
+                    .chain(b.0.iter())
+                    .filter_map(|element| element.data_type.span.as_ref())
+                    .collect(),
+                link_reason_note(reason)
+                    .map(String::from)
+                    .into_iter()
+                    .collect(),
+            ),
+            Error::UnwrappedOptional(value) => Self::new(
+                "unwrapped_optional",
+                format!("value of type {} may be null", value.value),
+                vec![&value.span],
+                vec![String::from(
+                    "Use `if var name = value { ... }` to access the value when it isn't null.",
+                )],
+            ),
+            Error::AssignToImmutable { name, declared_at } => Self::new(
+                "assign_to_immutable",
+                format!(
+                    "cannot assign twice to the immutable variable {}",
+                    name.value
+                ),
+                iter::once(&name.span).chain(declared_at.as_ref()).collect(),
+                vec![String::from(
+                    "Declare the variable with `var mut` to allow assigning to it.",
+                )],
+            ),
+            Error::CannotCaptureEnvironment { variable, function } => Self::new(
+                "cannot_capture_environment",
+                format!("cannot use {} inside of a nested function", variable.value),
+                vec![&variable.span, &function],
+                vec![String::from(
+                    "Nested functions can't capture variables, pass it as a parameter instead.",
+                )],
+            ),
+            Error::OutOfRange { value, range } => Self::new(
+                "out_of_range",
+                format!("{} is out of range for {}", value.value, range.value),
+                iter::once(&value.span).chain(range.span.as_ref()).collect(),
+                vec![String::from(
+                    "Both bounds of a ranged integer are inclusive.",
+                )],
+            ),
+            Error::UnknownLabel(label) => Self::new(
+                "unknown_label",
+                format!("label '{} doesn't exist", label.value),
+                vec![&label.span],
+                Vec::new(),
+            ),
+            Error::MissingField {
+                structure,
+                field_name,
+                instance,
+            } => Self::new(
+                "missing_field",
+                format!("field {} doesn't exist for {}", field_name, structure.value),
+                iter::once(&structure.span)
+                    .chain(instance.as_ref())
+                    .collect(),
+                Vec::new(),
+            ),
+            Error::InvalidFlow { span, construct } => Self::new(
+                "invalid_flow",
+                format!("{} expression isn't inside a loop", construct),
+                vec![&span],
+                Vec::new(),
+            ),
+            Error::MissingFields { structure, missing } => Self::new(
+                "missing_fields",
+                format!(
+                    "the instance of {} is missing the fields {}",
+                    structure.value,
+                    missing
+                        .iter()
+                        .map(|field| field.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                vec![&structure.span],
+                vec![String::from(
+                    "Every field of a structure must be given a value.",
+                )],
+            ),
+            Error::NonExhaustiveMatch { span, missing } => Self::new(
+                "non_exhaustive_match",
+                format!(
+                    "match doesn't handle the variants {}",
+                    missing
+                        .iter()
+                        .map(|variant| variant.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                vec![&span],
+                vec![String::from(
+                    "Every variant of an enumeration must be handled by an arm.",
+                )],
+            ),
+        };
+
+        Self { code, ..diagnostic }
+    }
+}
@@ -31,6 +31,8 @@ pub struct OptElement<T> {
 
 pub struct Errors {
     pub errors: Vec<Error>,
+    // Warnings don't stop compilation, unless the user asked for them to be treated as errors.
+    pub warnings: Vec<Warning>,
     pub sources: SourceMap,
 }
 
@@ -39,6 +41,19 @@ impl Errors {
         self.errors.is_empty()
     }
 
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Error> {
+        self.errors.iter()
+    }
+
+    // The sources of every file that was read, which are needed to display the spans of the errors.
+    pub fn sources(&self) -> &SourceMap {
+        &self.sources
+    }
+
     pub fn insert_source(&mut self, path: Intern<Utf8PathBuf>, contents: String) {
         self.sources.insert(path, contents)
     }
@@ -50,6 +65,85 @@ impl Errors {
     pub fn insert_error(&mut self, error: Error) {
         self.errors.push(error);
     }
+
+    pub fn insert_warning(&mut self, warning: Warning) {
+        self.warnings.push(warning);
+    }
+
+    // Diagnostics are found phase by phase, so this puts them in the order of the code they point at instead. Ones that
+    // don't point at any code come first, and ones pointing at the same place keep the order they were found in.
+    pub fn sort_by_span(&mut self) {
+        fn position(span: Option<&Span>) -> Option<(&str, usize)> {
+            span.map(|span| (span.path.as_str(), span.range.start))
+        }
+
+        self.errors
+            .sort_by(|a, b| position(a.primary_span()).cmp(&position(b.primary_span())));
+        self.warnings
+            .sort_by(|a, b| position(Some(a.span())).cmp(&position(Some(b.span()))));
+    }
+}
+
+impl IntoIterator for Errors {
+    type Item = Error;
+    type IntoIter = std::vec::IntoIter<Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Errors {
+    type Item = &'a Error;
+    type IntoIter = std::slice::Iter<'a, Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.iter()
+    }
+}
+
+#[derive(Debug)]
+pub enum Warning {
+    ShadowsFunction {
+        name: Name,
+        original: Span,
+        shadow: Span,
+    },
+    // The value is the one the condition always evaluates to.
+    ConstantCondition {
+        span: Span,
+        value: bool,
+    },
+    // An `@typeof` is debugging code, so it's reported with the type it resolved to.
+    TypeQuery {
+        span: Span,
+        data_type: Type,
+    },
+    // The span is the `else` branch of a conditional whose condition is always true.
+    UnreachableBranch {
+        span: Span,
+    },
+}
+
+impl Warning {
+    pub fn span(&self) -> &Span {
+        match self {
+            Warning::ShadowsFunction { shadow: span, .. }
+            | Warning::ConstantCondition { span, .. }
+            | Warning::TypeQuery { span, .. }
+            | Warning::UnreachableBranch { span } => span,
+        }
+    }
+
+    // Warnings share the explanations of errors, but are numbered separately.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Warning::ShadowsFunction { .. } => "W0001",
+            Warning::ConstantCondition { .. } => "W0002",
+            Warning::TypeQuery { .. } => "W0003",
+            Warning::UnreachableBranch { .. } => "W0004",
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -61,20 +155,43 @@ pub enum Reason {
 // Used to represent a token, or a language construct, such as a while loop.
 #[derive(Eq, PartialEq, Hash, Debug)]
 pub enum Pattern {
+    // A specific token, which is always written the same way, such as `}`.
     Token(Token),
+    // Anything described by what it is rather than how it's written, such as an identifier or a function.
     Construct(&'static str),
     Character(char),
 }
 
+impl Pattern {
+    // Tokens that carry a value, such as identifiers, can be written in many different ways, so they are described as
+    // constructs instead.
+    pub fn from_token(token: Token) -> Self {
+        match token {
+            Token::Int(_) => Pattern::Construct("integer"),
+            Token::Boolean(_) => Pattern::Construct("boolean"),
+            Token::String(_) => Pattern::Construct("string"),
+            Token::Name(_) => Pattern::Construct("identifier"),
+            Token::Label(_) => Pattern::Construct("label"),
+            Token::EndOfFile => Pattern::Construct("end of file"),
+            token => Pattern::Token(token),
+        }
+    }
+}
+
 impl Display for Pattern {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Pattern::Token(token) => Display::fmt(&token, f),
-            other => match other {
-                Pattern::Construct(construct) => f.write_fmt(format_args!("{}", construct)),
-                Pattern::Character(character) => f.write_fmt(format_args!("'{}'", character)),
-                _ => unreachable!(),
-            },
+            Pattern::Construct(construct) => {
+                let article = match construct.chars().next() {
+                    _ if *construct == "end of file" => "the",
+                    Some('a' | 'e' | 'i' | 'o') => "an",
+                    _ => "a",
+                };
+
+                f.write_fmt(format_args!("{} {}", article, construct))
+            }
+            Pattern::Character(character) => f.write_fmt(format_args!("'{}'", character)),
         }
     }
 }
@@ -86,9 +203,32 @@ pub struct ParseError {
     pub found: Option<Pattern>,
     pub reason: Reason,
     pub label: Option<&'static str>,
+    // Set for errors that aren't about an unexpected input, such as an integer literal that's out of range.
+    pub message: Option<String>,
+    // Set for errors that have a variant of their own, which is reported instead of the rest.
+    pub error: Option<Error>,
 }
 
 impl ParseError {
+    pub fn custom(span: Span, message: String) -> Self {
+        Self {
+            span,
+            expected: HashSet::new(),
+            found: None,
+            reason: Reason::Unexpected,
+            label: None,
+            message: Some(message),
+            error: None,
+        }
+    }
+
+    pub fn specific(error: Error, span: Span) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::custom(span, String::new())
+        }
+    }
+
     pub fn unify(mut self, other: Self) -> Self {
         assert_eq!(self.span, other.span);
 
@@ -98,6 +238,8 @@ impl ParseError {
             _ => self.reason,
         };
         self.expected = self.expected.into_iter().chain(other.expected).collect();
+        self.message = self.message.or(other.message);
+        self.error = self.error.or(other.error);
 
         self
     }
@@ -118,13 +260,15 @@ impl ErrorTrait<Token> for ParseError {
                 .into_iter()
                 .map(|token| {
                     token
-                        .map(Pattern::Token)
+                        .map(Pattern::from_token)
                         .unwrap_or_else(|| Pattern::Construct("end of file"))
                 })
                 .collect(),
-            found: found.map(Pattern::Token),
+            found: found.map(Pattern::from_token),
             reason: Reason::Unexpected,
             label: None,
+            message: None,
+            error: None,
         }
     }
 
@@ -137,18 +281,21 @@ impl ErrorTrait<Token> for ParseError {
     ) -> Self {
         Self {
             span,
-            expected: iter::once(expected).map(Pattern::Token).collect(),
-            found: found.map(Pattern::Token),
+            expected: iter::once(expected).map(Pattern::from_token).collect(),
+            found: found.map(Pattern::from_token),
             reason: Reason::UnclosedDelimiter(Element {
                 value: start,
                 span: start_span,
             }),
             label: None,
+            message: None,
+            error: None,
         }
     }
 
+    // Labels are added from the innermost parser outwards, and the innermost one is the most specific.
     fn with_label(mut self, label: Self::Label) -> Self {
-        self.label = Some(label);
+        self.label.get_or_insert(label);
         self
     }
 
@@ -179,11 +326,13 @@ impl ErrorTrait<char> for ParseError {
             found: found.map(Pattern::Character),
             reason: Reason::Unexpected,
             label: None,
+            message: None,
+            error: None,
         }
     }
 
     fn with_label(mut self, label: Self::Label) -> Self {
-        self.label = Some(label);
+        self.label.get_or_insert(label);
         self
     }
 
@@ -192,24 +341,54 @@ impl ErrorTrait<char> for ParseError {
     }
 }
 
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        if let Some(error) = error.error {
+            return error;
+        }
+
+        match error.message {
+            Some(message) => Error::BasicAt {
+                message,
+                span: error.span,
+            },
+            None => Error::Unexpected {
+                expected: error.expected,
+                found: Element {
+                    // If it's None, it must have encountered the end of file.
+                    value: error.found.unwrap_or(Pattern::Construct("end of file")),
+                    span: error.span,
+                },
+                reason: error.reason,
+                while_parsing: error.label.map(Pattern::Construct),
+            },
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TraceElement {
     pub reason: LinkReason,
     pub data_type: OptElement<Type>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Backtrace(pub Vec<TraceElement>);
 
 impl Backtrace {
     pub fn new() -> Self {
-        Self(Vec::new())
+        Self::default()
     }
 }
 
 #[derive(Debug)]
 pub enum Error {
     Basic(String),
+    // A basic error which can point at where in the source it happened.
+    BasicAt {
+        message: String,
+        span: Span,
+    },
     IO {
         error: IOError,
         action: Cow<'static, str>,
@@ -224,11 +403,17 @@ pub enum Error {
         expected: HashSet<Pattern>,
         found: Element<Pattern>,
         reason: Reason,
-        while_parsing: Option<&'static str>,
+        while_parsing: Option<Pattern>,
     },
+    // The paths are the file or directory of the first module with the name, and then of the one conflicting with it.
     ConflictingModuleNames {
         parent: Id,
         name: Name,
+        first: Utf8PathBuf,
+        second: Utf8PathBuf,
+    },
+    MissingEntryPoint {
+        expected: Utf8PathBuf,
     },
     InvalidAssign(Element<&'static str>),
     MissingId {
@@ -246,10 +431,589 @@ pub enum Error {
     },
     MissingField {
         structure: Element<Type>,
-        field_name: Id,
+        field_name: Name,
+        instance: Option<Span>,
     },
     InvalidFlow {
         span: Span,
         construct: &'static str,
     },
+    NonExhaustiveMatch {
+        span: Span,
+        missing: Vec<Name>,
+    },
+    UnwrappedOptional(Element<Type>),
+    UnknownLabel(Element<Name>),
+    MissingMain,
+    ArgumentCountMismatch {
+        expected: usize,
+        found: usize,
+        call_span: Span,
+        def_span: Span,
+    },
+    InvalidMainSignature(Element<Type>),
+    // The function is the nested function the variable was used in.
+    CannotCaptureEnvironment {
+        variable: Element<Name>,
+        function: Span,
+    },
+    // The range is the ranged integer type the value was used as.
+    OutOfRange {
+        value: Element<i32>,
+        range: OptElement<Type>,
+    },
+    // The structure is the name of the structure that was instantiated, and it's span is the whole instance.
+    MissingFields {
+        structure: Element<Id>,
+        missing: Vec<Name>,
+    },
+    // The path is a link to the target, which is a directory surrounding it.
+    ModuleCycle {
+        path: Utf8PathBuf,
+        target: Utf8PathBuf,
+    },
+    // The span is the condition of the assertion, which is always false.
+    AssertionFailed {
+        span: Span,
+    },
+    // The name is the assigned variable, and declared at is where it was declared, if it's known.
+    AssignToImmutable {
+        name: Element<Name>,
+        declared_at: Option<Span>,
+    },
+    // The span covers the whole chain, such as `a < b < c`.
+    ChainedComparison {
+        span: Span,
+    },
+    // The construct is described in a way that reads as the subject of a sentence, such as "the length of a string that
+    // isn't constant". The span is where it was used.
+    Unsupported {
+        construct: &'static str,
+        span: Span,
+    },
+    // The span is the header of the function, from `func` up to where the body should have started.
+    MissingFunctionBody {
+        function_span: Span,
+    },
+    // The span is the expression whose type would have had to contain itself.
+    InfiniteType {
+        span: Span,
+    },
+    // The span is the operator whose constant result doesn't fit in an integer.
+    ConstantOverflow {
+        span: Span,
+    },
+    // The name is one of the primitive types, and the span is where a variable or parameter was given it.
+    ReservedTypeName {
+        name: Name,
+        span: Span,
+    },
+}
+
+impl Error {
+    // The declarations may be found in any order, such as a new variable being checked against an existing one, so the
+    // one written earlier in the file is always made the first.
+    pub fn conflicting_ids(a: Span, b: Span, id: Id) -> Self {
+        let (first, second) = if a.path == b.path && b.range.start < a.range.start {
+            (b, a)
+        } else {
+            (a, b)
+        };
+
+        Error::ConflictingIds { first, second, id }
+    }
+
+    // The span an error is mainly about, which is the one the CLI starts it's report at.
+    pub fn primary_span(&self) -> Option<&Span> {
+        match self {
+            Error::Basic(_)
+            | Error::IO { .. }
+            | Error::ConflictingModuleNames { .. }
+            | Error::MissingEntryPoint { .. }
+            | Error::ModuleCycle { .. }
+            | Error::MissingMain => None,
+            Error::BasicAt { span, .. }
+            | Error::UnterminatedBlockComment { span }
+            | Error::UnterminatedString { span }
+            | Error::InvalidFlow { span, .. }
+            | Error::NonExhaustiveMatch { span, .. }
+            | Error::AssertionFailed { span }
+            | Error::ChainedComparison { span }
+            | Error::Unsupported { span, .. }
+            | Error::InfiniteType { span }
+            | Error::ConstantOverflow { span }
+            | Error::ReservedTypeName { span, .. }
+            | Error::MissingFunctionBody {
+                function_span: span,
+            }
+            | Error::ConflictingIds { second: span, .. }
+            | Error::ArgumentCountMismatch {
+                call_span: span, ..
+            }
+            | Error::CannotCaptureEnvironment {
+                variable: Element { span, .. },
+                ..
+            }
+            | Error::AssignToImmutable {
+                name: Element { span, .. },
+                ..
+            }
+            | Error::OutOfRange {
+                value: Element { span, .. },
+                ..
+            }
+            | Error::MissingFields {
+                structure: Element { span, .. },
+                ..
+            }
+            | Error::MissingField {
+                structure: Element { span, .. },
+                ..
+            }
+            | Error::Unexpected {
+                found: Element { span, .. },
+                ..
+            }
+            | Error::InvalidAssign(Element { span, .. })
+            | Error::MissingId {
+                id: Element { span, .. },
+            }
+            | Error::UnwrappedOptional(Element { span, .. })
+            | Error::UnknownLabel(Element { span, .. })
+            | Error::InvalidMainSignature(Element { span, .. }) => Some(span),
+            // The mismatch is reported where the second type was found, if it's known.
+            Error::TypeMismatch { a, b, .. } => b.0[0]
+                .data_type
+                .span
+                .as_ref()
+                .or(a.0[0].data_type.span.as_ref()),
+        }
+    }
+
+    // A stable code for every kind of error, which can be explained using `--explain`. New errors get the next free
+    // code, and codes are never reused. Errors which are too general to explain don't have a code.
+    pub fn code(&self) -> Option<&'static str> {
+        Some(match self {
+            Error::Basic(_) | Error::BasicAt { .. } | Error::IO { .. } => return None,
+            Error::UnterminatedBlockComment { .. } => "E0001",
+            Error::UnterminatedString { .. } => "E0002",
+            Error::Unexpected { .. } => "E0003",
+            Error::ConflictingModuleNames { .. } => "E0004",
+            Error::MissingEntryPoint { .. } => "E0005",
+            Error::InvalidAssign(_) => "E0006",
+            Error::MissingId { .. } => "E0007",
+            Error::ConflictingIds { .. } => "E0008",
+            Error::TypeMismatch { .. } => "E0009",
+            Error::MissingField { .. } => "E0010",
+            Error::InvalidFlow { .. } => "E0011",
+            Error::NonExhaustiveMatch { .. } => "E0012",
+            Error::UnwrappedOptional(_) => "E0013",
+            Error::UnknownLabel(_) => "E0014",
+            Error::MissingMain => "E0015",
+            Error::ArgumentCountMismatch { .. } => "E0016",
+            Error::InvalidMainSignature(_) => "E0017",
+            Error::CannotCaptureEnvironment { .. } => "E0018",
+            Error::OutOfRange { .. } => "E0019",
+            Error::ModuleCycle { .. } => "E0020",
+            Error::MissingFields { .. } => "E0021",
+            Error::AssertionFailed { .. } => "E0022",
+            Error::AssignToImmutable { .. } => "E0023",
+            Error::ChainedComparison { .. } => "E0024",
+            Error::Unsupported { .. } => "E0026",
+            Error::MissingFunctionBody { .. } => "E0027",
+            Error::InfiniteType { .. } => "E0028",
+            Error::ConstantOverflow { .. } => "E0029",
+            Error::ReservedTypeName { .. } => "E0030",
+        })
+    }
+}
+
+// The CLI renders errors using ariadne, these let tools which standardize on miette render them instead.
+#[cfg(feature = "miette")]
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Basic(message) | Error::BasicAt { message, .. } => write!(f, "{}", message),
+            Error::IO { error, action } => write!(f, "failed to {} because {}", action, error),
+            Error::UnterminatedBlockComment { .. } => write!(f, "unterminated block comment"),
+            Error::UnterminatedString { .. } => write!(f, "unterminated string"),
+            Error::Unexpected {
+                expected,
+                found,
+                while_parsing,
+                ..
+            } => {
+                write!(
+                    f,
+                    "expected {} but found {}",
+                    expected
+                        .iter()
+                        .map(|pattern| pattern.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" or "),
+                    found.value
+                )?;
+
+                match while_parsing {
+                    Some(while_parsing) => write!(f, " while parsing {}", while_parsing),
+                    None => Ok(()),
+                }
+            }
+            Error::ConflictingModuleNames {
+                parent,
+                name,
+                first,
+                second,
+            } => write!(
+                f,
+                "the child module {} exists more than once in the parent module {}, at {} and at {}",
+                name, parent, first, second
+            ),
+            Error::MissingEntryPoint { expected } => write!(
+                f,
+                "the project has no entry point, expected it at {}",
+                expected
+            ),
+            Error::InvalidAssign(location) => write!(f, "cannot assign to a {}", location.value),
+            Error::MissingId { id } => write!(f, "cannot find {} in scope", id.value),
+            Error::ConflictingIds { id, .. } => {
+                write!(f, "the name {} is conflicted between two identifiers", id)
+            }
+            Error::TypeMismatch { a, b, .. } => write!(
+                f,
+                "type mismatch between {} and {}",
+                a.0[0].data_type.value, b.0[0].data_type.value
+            ),
+            Error::MissingField {
+                structure,
+                field_name,
+                ..
+            } => write!(
+                f,
+                "field {} doesn't exist for {}",
+                field_name, structure.value
+            ),
+            Error::InvalidFlow { construct, .. } => {
+                write!(f, "{} expression isn't inside a loop", construct)
+            }
+            Error::NonExhaustiveMatch { missing, .. } => write!(
+                f,
+                "match doesn't handle the variants {}",
+                missing
+                    .iter()
+                    .map(|variant| variant.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Error::UnwrappedOptional(value) => {
+                write!(f, "value of type {} may be null", value.value)
+            }
+            Error::UnknownLabel(label) => write!(f, "label '{} doesn't exist", label.value),
+            Error::MissingMain => {
+                write!(f, "the project has no `main` function in it's entry module")
+            }
+            Error::ArgumentCountMismatch {
+                expected, found, ..
+            } => write!(f, "expected {} arguments but found {}", expected, found),
+            Error::InvalidMainSignature(main) => {
+                write!(f, "the `main` function is of type {}", main.value)
+            }
+            Error::CannotCaptureEnvironment { variable, .. } => write!(
+                f,
+                "cannot use {} inside of a nested function",
+                variable.value
+            ),
+            Error::OutOfRange { value, range } => {
+                write!(f, "{} is out of range for {}", value.value, range.value)
+            }
+            Error::MissingFields { structure, missing } => write!(
+                f,
+                "the instance of {} is missing the fields {}",
+                structure.value,
+                missing
+                    .iter()
+                    .map(|field| field.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Error::ModuleCycle { path, target } => write!(
+                f,
+                "the directory {} is a link to {}, which contains it",
+                path, target
+            ),
+            Error::AssertionFailed { .. } => write!(f, "assertion is always false"),
+            Error::ChainedComparison { .. } => write!(f, "comparison operators cannot be chained"),
+            Error::Unsupported { construct, .. } => write!(f, "{} isn't supported yet", construct),
+            Error::MissingFunctionBody { .. } => write!(f, "this function has no body"),
+            Error::InfiniteType { .. } => write!(f, "this type would contain itself"),
+            Error::ConstantOverflow { .. } => write!(f, "this arithmetic overflows"),
+            Error::ReservedTypeName { name, .. } => {
+                write!(f, "{} is the name of a type, so it can't name a variable", name)
+            }
+            Error::AssignToImmutable { name, .. } => {
+                write!(
+                    f,
+                    "cannot assign twice to the immutable variable {}",
+                    name.value
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "miette")]
+impl std::error::Error for Error {}
+
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Error::code(self).map(|code| Box::new(code) as Box<dyn Display>)
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(miette::Severity::Error)
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let help = match self {
+            Error::UnterminatedBlockComment { .. } => {
+                "A block comment termination looks like `*/`."
+            }
+            Error::UnterminatedString { .. } => "A string termination looks like `\"`.",
+            Error::MissingMain => "Use `--lib` if the project isn't meant to be executed.",
+            Error::InvalidMainSignature(_) => {
+                "The `main` function must take no parameters and return Unit."
+            }
+            Error::InvalidAssign(_) => "Only identifiers and fields can be assigned to.",
+            Error::ConflictingIds { .. } => {
+                "To avoid ambiguities, non-variable identifiers must be unique in their scope."
+            }
+            Error::UnwrappedOptional(_) => {
+                "Use `if var name = value { ... }` to access the value when it isn't null."
+            }
+            Error::CannotCaptureEnvironment { .. } => {
+                "Nested functions can't capture variables, pass it as a parameter instead."
+            }
+            Error::OutOfRange { .. } => "Both bounds of a ranged integer are inclusive.",
+            Error::AssignToImmutable { .. } => {
+                "Declare the variable with `var mut` to allow assigning to it."
+            }
+            Error::ChainedComparison { .. } => {
+                "Compare each pair on it's own, such as `a < b && b < c`."
+            }
+            Error::Unsupported { .. } => {
+                "The compiler can't generate code for this yet, so it must be written another way."
+            }
+            Error::MissingFunctionBody { .. } => "Add a body after the function, such as `{}`.",
+            Error::InfiniteType { .. } => {
+                "A value can't be used as part of itself, give it a type explicitly to find the mistake."
+            }
+            Error::ConstantOverflow { .. } => {
+                "Integers range from -2147483648 to 2147483647, inclusive."
+            }
+            Error::ReservedTypeName { .. } => {
+                "Type hints would still refer to the type, so give the variable another name."
+            }
+            Error::MissingFields { .. } => "Every field of a structure must be given a value.",
+            Error::NonExhaustiveMatch { .. } => {
+                "Every variant of an enumeration must be handled by an arm."
+            }
+            _ => return None,
+        };
+
+        Some(Box::new(help))
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let spans: Vec<(&Span, Option<&str>)> = match self {
+            Error::Basic(_)
+            | Error::IO { .. }
+            | Error::ConflictingModuleNames { .. }
+            | Error::MissingEntryPoint { .. }
+            | Error::ModuleCycle { .. }
+            | Error::MissingMain => return None,
+            Error::BasicAt { span, .. }
+            | Error::UnterminatedBlockComment { span }
+            | Error::UnterminatedString { span }
+            | Error::InvalidFlow { span, .. }
+            | Error::NonExhaustiveMatch { span, .. }
+            | Error::AssertionFailed { span }
+            | Error::ChainedComparison { span }
+            | Error::Unsupported { span, .. }
+            | Error::InfiniteType { span }
+            | Error::ConstantOverflow { span }
+            | Error::ReservedTypeName { span, .. }
+            | Error::MissingFunctionBody {
+                function_span: span,
+            } => vec![(span, None)],
+            Error::Unexpected { found, reason, .. } => iter::once((&found.span, None))
+                .chain(match reason {
+                    Reason::UnclosedDelimiter(delimiter) => {
+                        Some((&delimiter.span, Some("The delimiter was opened here.")))
+                    }
+                    Reason::Unexpected => None,
+                })
+                .collect(),
+            Error::InvalidAssign(Element { span, .. })
+            | Error::MissingId {
+                id: Element { span, .. },
+            }
+            | Error::UnwrappedOptional(Element { span, .. })
+            | Error::MissingFields {
+                structure: Element { span, .. },
+                ..
+            }
+            | Error::UnknownLabel(Element { span, .. }) => vec![(span, None)],
+            Error::ConflictingIds { first, second, .. } => vec![
+                (first, Some("It's first declared here.")),
+                (second, Some("It's declared again here.")),
+            ],
+            Error::TypeMismatch { a, b, .. } => {
+                a.0.iter()
+                    .chain(b.0.iter())
+                    .filter_map(|element| element.data_type.span.as_ref())
+                    .map(|span| (span, None))
+                    .collect()
+            }
+            Error::MissingField {
+                structure,
+                instance,
+                ..
+            } => iter::once((&structure.span, None))
+                .chain(
+                    instance
+                        .iter()
+                        .map(|instance| (instance, Some("The instance was created here."))),
+                )
+                .collect(),
+            Error::ArgumentCountMismatch {
+                call_span,
+                def_span,
+                ..
+            } => vec![
+                (call_span, Some("The function is called here.")),
+                (def_span, Some("The function is declared here.")),
+            ],
+            Error::InvalidMainSignature(main) => {
+                vec![(&main.span, Some("This function is the entry point."))]
+            }
+            Error::CannotCaptureEnvironment { variable, function } => vec![
+                (
+                    &variable.span,
+                    Some("This is a local of a surrounding function."),
+                ),
+                (function, Some("It's used inside of this function.")),
+            ],
+            Error::AssignToImmutable { name, declared_at } => iter::once((&name.span, None))
+                .chain(
+                    declared_at
+                        .iter()
+                        .map(|span| (span, Some("The variable is declared here."))),
+                )
+                .collect(),
+            Error::OutOfRange { value, range } => iter::once((&value.span, None))
+                .chain(
+                    range
+                        .span
+                        .iter()
+                        .map(|span| (span, Some("The range is declared here."))),
+                )
+                .collect(),
+        };
+
+        Some(Box::new(spans.into_iter().map(|(span, label)| {
+            miette::LabeledSpan::new_with_span(label.map(String::from), span)
+        })))
+    }
+}
+
+#[cfg(feature = "miette")]
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::ShadowsFunction { name, .. } => {
+                write!(f, "the variable {} shadows a function", name)
+            }
+            Warning::ConstantCondition { value, .. } => {
+                write!(f, "this condition is always {}", value)
+            }
+            Warning::TypeQuery { data_type, .. } => {
+                write!(f, "this value is of type {}", data_type)
+            }
+            Warning::UnreachableBranch { .. } => write!(f, "this branch is unreachable"),
+        }
+    }
+}
+
+#[cfg(feature = "miette")]
+impl std::error::Error for Warning {}
+
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for Warning {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(Warning::code(self)))
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(miette::Severity::Warning)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let spans = match self {
+            Warning::ShadowsFunction {
+                original, shadow, ..
+            } => vec![
+                (shadow, Some("The variable is declared here.")),
+                (
+                    original,
+                    Some("This function can't be used by it's name after it."),
+                ),
+            ],
+            Warning::ConstantCondition { span, .. }
+            | Warning::TypeQuery { span, .. }
+            | Warning::UnreachableBranch { span } => vec![(span, None)],
+        };
+
+        Some(Box::new(spans.into_iter().map(|(span, label)| {
+            miette::LabeledSpan::new_with_span(label.map(String::from), span)
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use camino::Utf8PathBuf;
+    use internment::Intern;
+
+    use super::Error;
+    use crate::core::{ast::Id, span::Span};
+
+    fn span(range: Range<usize>) -> Span {
+        Span {
+            path: Intern::new(Utf8PathBuf::from("main.bell")),
+            range,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn conflicts_label_the_earlier_declaration_first() {
+        let id = Id::new_single(Intern::new("point".to_string()));
+
+        for conflict in [
+            Error::conflicting_ids(span(30..35), span(4..9), id.clone()),
+            Error::conflicting_ids(span(4..9), span(30..35), id),
+        ] {
+            assert!(
+                matches!(
+                    &conflict,
+                    Error::ConflictingIds { first, second, .. } if first.range == (4..9) && second.range == (30..35)
+                ),
+                "{:?}",
+                conflict
+            );
+        }
+    }
 }
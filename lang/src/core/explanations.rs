@@ -0,0 +1,464 @@
+// Longer explanations of the errors and warnings, keyed by their codes. See `Error::code` and `Warning::code` for which
+// code belongs to which error or warning.
+const EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        "E0001",
+        "A block comment was opened, but never closed.
+
+Every `/*` must be matched by a later `*/`:
+
+    /* This comment never ends
+    func main() {}
+
+Close the comment where it is meant to end:
+
+    /* This comment ends here */
+    func main() {}",
+    ),
+    (
+        "E0002",
+        "A string was opened, but never closed.
+
+Every string must end with a `\"` before the end of the file:
+
+    var name = \"Bell;
+
+Close the string where it is meant to end:
+
+    var name = \"Bell\";",
+    ),
+    (
+        "E0003",
+        "The parser found something it didn't expect.
+
+This usually means a token is missing or misplaced, such as a missing `;` in between two
+expressions of a block:
+
+    func main() {
+        var a = 1
+        var b = 2;
+    }
+
+The error lists what the parser expected to find instead.",
+    ),
+    (
+        "E0004",
+        "Two modules in the same parent module have the same name.
+
+A module is named after it's file or directory, without the extension. A directory `shapes` containing
+both `circle.bell` and a directory named `circle` has two modules called `circle`, and one of them
+must be renamed.",
+    ),
+    (
+        "E0005",
+        "A project directory has no entry point.
+
+The root module of a project is it's `main.bell` file, and every other file or directory in the
+project is a submodule of it. Add a `main.bell` file to the root of the project.",
+    ),
+    (
+        "E0006",
+        "Something was assigned to that isn't a place in memory.
+
+Only variables, fields of them and dereferenced references can be assigned to:
+
+    func main() {
+        1 = 2;
+    }
+
+Assign to a variable instead:
+
+    func main() {
+        var mut a = 1;
+        a = 2;
+    }",
+    ),
+    (
+        "E0007",
+        "An identifier was used, but nothing with that name is in scope.
+
+    func main() {
+        var a = b;
+    }
+
+Make sure the name is spelled correctly, that it was declared before it's use, and that it's module
+was imported with `use` if it comes from another module.",
+    ),
+    (
+        "E0008",
+        "Two items in the same scope have the same name.
+
+Functions, structures and enumerations can't be shadowed:
+
+    func area() {}
+    func area() {}
+
+Rename one of them.",
+    ),
+    (
+        "E0009",
+        "A value of one type was used where another type was expected.
+
+    func main() {
+        var a: Int = true;
+    }
+
+The error shows where each of the two types came from, and a note explains why they needed to match.",
+    ),
+    (
+        "E0010",
+        "A field was accessed on a structure which doesn't have it.
+
+    struct Point { x: Int, y: Int }
+
+    func main() {
+        var point = Point { x: 1, y: 2 };
+        var z = point.z;
+    }
+
+Make sure the field is spelled correctly, or add it to the structure.",
+    ),
+    (
+        "E0011",
+        "A `break` or `continue` was used outside of a loop.
+
+    func main() {
+        break 1;
+    }
+
+These may only be used inside of a `loop`. Use `return` to exit a function early.",
+    ),
+    (
+        "E0012",
+        "A match expression doesn't handle every variant of an enumeration.
+
+    enum Shape { Circle(Int), Square(Int) }
+
+    func area(shape: Shape) -> Int {
+        match shape {
+            Shape::Circle(radius) => 3 * radius * radius,
+        }
+    }
+
+Add an arm for every missing variant.",
+    ),
+    (
+        "E0013",
+        "An optional value was used where a value of it's inner type was expected.
+
+An optional may be `null`, so it must be unwrapped before it's value can be used:
+
+    func double(value: Int?) -> Int {
+        value * 2
+    }
+
+Bind it's value in a conditional, which handles the case in which it is `null`:
+
+    func double(value: Int?) -> Int {
+        if var value = value {
+            value * 2
+        } else {
+            0
+        }
+    }",
+    ),
+    (
+        "E0014",
+        "A `break` or `continue` referred to a label that no surrounding loop has.
+
+    func main() {
+        'outer: loop {
+            break 'inner 1;
+        }
+    }
+
+Labels are only visible inside of the loop they name, and not inside of functions declared in it.",
+    ),
+    (
+        "E0015",
+        "An executable project has no `main` function.
+
+The `main` function is where execution starts, and must be declared in the entry module:
+
+    func main() {}
+
+If the project isn't meant to be executed, check it as a library using `--lib`.",
+    ),
+    (
+        "E0016",
+        "A function was called with the wrong number of arguments.
+
+    func add(a: Int, b: Int) -> Int {
+        a + b
+    }
+
+    func main() {
+        add(1);
+    }
+
+Pass exactly one argument for every parameter of the function.",
+    ),
+    (
+        "E0017",
+        "The `main` function has an invalid signature.
+
+The `main` function can't take any parameters, and may only return the unit type:
+
+    func main(argument: Int) {}
+
+Remove it's parameters and return type:
+
+    func main() {}",
+    ),
+    (
+        "E0018",
+        "A nested function used a local variable of a function surrounding it.
+
+Nested functions can't capture the environment they are declared in:
+
+    func main() {
+        var offset = 2;
+
+        func shift(value: Int) -> Int {
+            value + offset
+        }
+    }
+
+Pass the variable to the nested function as a parameter instead:
+
+    func main() {
+        var offset = 2;
+
+        func shift(value: Int, offset: Int) -> Int {
+            value + offset
+        }
+    }",
+    ),
+    (
+        "E0019",
+        "A constant integer was used as a ranged integer, but it is outside of it's range.
+
+    func main() {
+        var percent: Int<0, 100> = 120;
+    }
+
+Both bounds of a ranged integer are inclusive. Use a value inside of the range, or widen the range of the type:
+
+    func main() {
+        var percent: Int<0, 200> = 120;
+    }",
+    ),
+    (
+        "E0020",
+        "A directory in a project is a symbolic link to a directory surrounding it.
+
+Every directory in a project is a submodule, so a link to a directory that contains it would make the
+module contain itself forever:
+
+    project/
+        main.bell
+        shapes/
+            circle.bell
+            all -> ../
+
+Remove the link, or make it point to a directory outside of the ones surrounding it.",
+    ),
+    (
+        "E0021",
+        "An instance of a structure didn't give a value to some of it's fields.
+
+    struct Point { x: Int, y: Int }
+
+    func main() {
+        var point = Point { x: 1 };
+    }
+
+Every field must be given a value when the structure is instantiated:
+
+    func main() {
+        var point = Point { x: 1, y: 2 };
+    }",
+    ),
+    (
+        "E0022",
+        "The condition of an assertion is always false.
+
+    func main() {
+        @assert(1 > 2);
+    }
+
+Conditions which are known while compiling are checked by the compiler, so an assertion that can never hold stops
+compilation instead of failing when the project runs. Fix the condition, or remove the assertion:
+
+    func main() {
+        @assert(2 > 1);
+    }",
+    ),
+    (
+        "E0023",
+        "A variable was assigned to, but it wasn't declared as mutable.
+
+    func main() {
+        var count = 0;
+        count = count + 1;
+    }
+
+Variables are immutable unless they are declared with `var mut`. The same goes for parameters, bindings
+of patterns and functions, which can never be assigned to:
+
+    func main() {
+        var mut count = 0;
+        count = count + 1;
+    }",
+    ),
+    (
+        "E0024",
+        "Comparison operators were chained, such as in `a < b < c`.
+
+    func main() {
+        var middle = 0 < 5 < 10;
+    }
+
+The first comparison is made before the second, so the second one would compare a Bool to the
+right side. Compare each pair on it's own instead:
+
+    func main() {
+        var middle = 0 < 5 && 5 < 10;
+    }",
+    ),
+    (
+        "E0026",
+        "Something was used which the compiler can't generate code for yet.
+
+The program is valid, but it can't be compiled until support for it is added. For example, the length of a
+string is only known while compiling if the string is a literal:
+
+    func greeting(name: Str) -> Int {
+        len(\"Hello, {name}!\")
+    }
+
+A string literal has a known length, so the call is replaced by it:
+
+    func greeting() -> Int {
+        len(\"Hello!\")
+    }",
+    ),
+    (
+        "E0027",
+        "A function was declared without a body.
+
+The parameters and return type of a function must be followed by a block:
+
+    func area(width: Int, height: Int) -> Int
+
+Add the body, even if it's empty:
+
+    func area(width: Int, height: Int) -> Int {
+        width * height
+    }",
+    ),
+    (
+        "E0028",
+        "A type was inferred to contain itself.
+
+Types are inferred from how values are used. Calling a parameter with itself means it's type is a function which
+takes it's own type as a parameter, which could only be written out by going on forever:
+
+    var apply = |f| f(f)
+
+Such a type can't exist, so the value was likely used in the wrong place. Giving it a type explicitly shows where the
+mistake is.",
+    ),
+    (
+        "E0029",
+        "Arithmetic on constant integers overflowed.
+
+Arithmetic whose operands are all known while compiling is evaluated while compiling. Integers range from
+-2147483648 to 2147483647, so a result outside of that range can't be represented:
+
+    var area = 1000000 * 1000000
+
+Either use smaller values, or split the computation so that no intermediate result is out of range.",
+    ),
+    (
+        "E0030",
+        "A variable or parameter was named after a primitive type.
+
+The names `Int`, `I8`, `I16`, `I64`, `Bool` and `Str` always refer to their types in type hints, so a
+variable with one of them could never be named in one, and would only make the hints confusing:
+
+    var Int = 5
+    var count: Int = Int
+
+Give the variable another name.",
+    ),
+    (
+        "W0001",
+        "A variable shadows a function.
+
+Shadowing is allowed, but the function can't be used by it's name for as long as the variable is in scope:
+
+    func area(radius: Int) -> Int {
+        3 * radius * radius
+    }
+
+    func main() {
+        var area = area(2);
+        var other = area(3);
+    }
+
+Rename the variable if the function is still needed after it.",
+    ),
+    (
+        "W0002",
+        "The condition of a conditional is a constant, so the same branch is always taken.
+
+    func main() {
+        if true {
+            1
+        } else {
+            2
+        };
+    }
+
+The branch that is never taken is still checked. Remove the conditional and keep only the branch that is
+taken.",
+    ),
+    (
+        "W0003",
+        "An `@typeof` query reports the type that was inferred for it's value.
+
+    func main() {
+        var list = null;
+        var query = @typeof(list);
+    }
+
+It's meant for understanding what was inferred while debugging, so remove it once it isn't needed.",
+    ),
+    (
+        "W0004",
+        "The `else` branch of a conditional can never be taken, since the condition is always true.
+
+    func limit() -> Int {
+        if 10 > 5 {
+            10
+        } else {
+            5
+        }
+    }
+
+The branch is still checked, so it must be valid, but it's almost always a mistake in the condition. Fix
+the condition, or remove the conditional:
+
+    func limit() -> Int {
+        10
+    }",
+    ),
+];
+
+pub fn explain(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(other, _)| other.eq_ignore_ascii_case(code))
+        .map(|&(_, explanation)| explanation)
+}
@@ -1,12 +1,18 @@
 use camino::Utf8PathBuf;
+#[cfg(feature = "fs")]
 use std::borrow::Cow;
-use std::fs;
+#[cfg(feature = "fs")]
+use std::iter;
 
 use internment::Intern;
 
+#[cfg(feature = "fs")]
 use crate::core::error::Error;
 
+#[cfg(feature = "fs")]
 use super::error::Errors;
+#[cfg(feature = "fs")]
+use super::span::SourceMap;
 
 pub enum Entry {
     Directory {
@@ -19,79 +25,260 @@ pub enum Entry {
     },
 }
 
-impl Entry {
-    pub fn from(path: Utf8PathBuf, errors: &mut Errors) -> Option<Entry> {
-        Some(Entry::Directory {
-            entries: match std::fs::read_dir(&path) {
+// The files and directories of a project, before any of the files are read. The project is walked once into a layout,
+// which is then used both to load the sources and to build the entries.
+#[cfg(feature = "fs")]
+pub enum Layout {
+    Directory {
+        path: Intern<Utf8PathBuf>,
+        entries: Vec<Layout>,
+    },
+    File(Intern<Utf8PathBuf>),
+}
+
+// A directory which is still being walked. The entries are `None` if the directory couldn't be read, in which case it's
+// left out of it's parent.
+#[cfg(feature = "fs")]
+struct PendingDirectory {
+    path: Intern<Utf8PathBuf>,
+    canonical: Utf8PathBuf,
+    parent: Option<usize>,
+    entries: Option<Vec<Layout>>,
+}
+
+#[cfg(feature = "fs")]
+impl Layout {
+    // The root may either be a single file or a project directory. The directories are walked using an explicit stack
+    // rather than recursion, so that deeply nested modules can't overflow the stack.
+    pub fn walk(path: Utf8PathBuf, errors: &mut Errors) -> Option<Layout> {
+        if path.is_file() {
+            return Some(Layout::File(Intern::new(path)));
+        }
+
+        let canonical = match path.canonicalize_utf8() {
+            Ok(canonical) => canonical,
+            Err(io_error) => {
+                errors.insert_error(Error::IO {
+                    error: io_error,
+                    action: Cow::from("get the root module directory"),
+                });
+
+                return None;
+            }
+        };
+
+        let mut directories = vec![PendingDirectory {
+            path: Intern::new(path),
+            canonical,
+            parent: None,
+            entries: None,
+        }];
+        let mut stack = vec![0];
+
+        while let Some(index) = stack.pop() {
+            let path = directories[index].path;
+            let directory = match std::fs::read_dir(path.as_std_path()) {
+                Ok(directory) => directory,
                 Err(io_error) => {
                     errors.insert_error(Error::IO {
                         error: io_error,
-                        action: Cow::from("get the root module directory"),
+                        action: Cow::from(format!("read the directory `{}`", path)),
                     });
 
-                    return None;
+                    continue;
                 }
-                Ok(directory) => directory
-                    .filter_map(|entry| {
-                        let entry = match entry {
-                            Ok(entry) => match Utf8PathBuf::from_path_buf(entry.path()) {
-                                Ok(path) => Some(path),
-                                Err(path) => {
-                                    errors.insert_error(Error::Basic(format!(
-                                        "the path {} is not encoded in UTF-8",
-                                        path.to_string_lossy()
-                                    )));
-
-                                    return None;
-                                }
-                            },
-                            Err(io_error) => {
-                                errors.insert_error(Error::IO {
-                                    error: io_error,
-                                    action: Cow::from("get an entries path in a directory"),
-                                });
-
-                                return None;
-                            }
-                        };
-
-                        entry
-                            .map(|path| {
-                                Some(if path.is_file() {
-                                    // The compiler will ignore files that don't have the `bell` extension.
-                                    // This is done so you can store utility files inside modules.
-                                    if path.extension().unwrap() == "bell" {
-                                        let path = Intern::new(path);
-
-                                        Entry::File {
-                                            path,
-                                            contents: match fs::read_to_string(&*path) {
-                                                Ok(text) => text,
-                                                Err(io_error) => {
-                                                    errors.insert_error(Error::IO {
-                                                        error: io_error,
-                                                        action: Cow::from(format!(
-                                                            "read the contents of the path `{}`",
-                                                            path
-                                                        )),
-                                                    });
-
-                                                    return None;
-                                                }
-                                            },
-                                        }
-                                    } else {
-                                        return None;
-                                    }
-                                } else {
-                                    Self::from(path, errors)?
-                                })
-                            })
-                            .flatten()
-                    })
-                    .collect(),
-            },
-            path: Intern::new(path),
-        })
+            };
+
+            let mut entries = Vec::new();
+
+            for entry in directory {
+                let path = match entry {
+                    Ok(entry) => match Utf8PathBuf::from_path_buf(entry.path()) {
+                        Ok(path) => path,
+                        Err(path) => {
+                            errors.insert_error(Error::Basic(format!(
+                                "the path {} is not encoded in UTF-8",
+                                path.to_string_lossy()
+                            )));
+
+                            continue;
+                        }
+                    },
+                    Err(io_error) => {
+                        errors.insert_error(Error::IO {
+                            error: io_error,
+                            action: Cow::from("get an entries path in a directory"),
+                        });
+
+                        continue;
+                    }
+                };
+
+                if path.is_file() {
+                    // The compiler will ignore files that don't have the `bell` extension.
+                    // This is done so you can store utility files inside modules.
+                    if path.extension() == Some("bell") {
+                        entries.push(Layout::File(Intern::new(path)));
+                    }
+                } else {
+                    let canonical = match path.canonicalize_utf8() {
+                        Ok(canonical) => canonical,
+                        Err(io_error) => {
+                            errors.insert_error(Error::IO {
+                                error: io_error,
+                                action: Cow::from(format!("read the directory `{}`", path)),
+                            });
+
+                            continue;
+                        }
+                    };
+
+                    // Symbolic links may point to a directory surrounding them, which would make the walk go on
+                    // forever. Only an ancestor makes a cycle, since two links to the same sibling directory are just
+                    // two modules with the same contents.
+                    if iter::successors(Some(index), |&index| directories[index].parent)
+                        .any(|index| directories[index].canonical == canonical)
+                    {
+                        errors.insert_error(Error::ModuleCycle {
+                            path,
+                            target: canonical,
+                        });
+
+                        continue;
+                    }
+
+                    directories.push(PendingDirectory {
+                        path: Intern::new(path),
+                        canonical,
+                        parent: Some(index),
+                        entries: None,
+                    });
+                    stack.push(directories.len() - 1);
+                }
+            }
+
+            directories[index].entries = Some(entries);
+        }
+
+        // Directories are always pushed after their parents, so going from the last one backwards only ever moves
+        // directories that were already completed into their parents.
+        while let Some(directory) = directories.pop() {
+            let layout = directory.entries.map(|entries| Layout::Directory {
+                path: directory.path,
+                entries,
+            });
+
+            match directory.parent {
+                Some(parent) => {
+                    if let (Some(layout), Some(entries)) = (layout, &mut directories[parent].entries)
+                    {
+                        entries.push(layout);
+                    }
+                }
+                None => return layout,
+            }
+        }
+
+        None
+    }
+
+    pub fn files(&self) -> Vec<Intern<Utf8PathBuf>> {
+        let mut files = Vec::new();
+        let mut stack = vec![self];
+
+        while let Some(layout) = stack.pop() {
+            match layout {
+                Layout::Directory { entries, .. } => stack.extend(entries),
+                Layout::File(path) => files.push(*path),
+            }
+        }
+
+        files
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Entry {
+    // Files that were already loaded into `sources` are taken from it, any other file is read directly. Like the walk
+    // itself, this uses an explicit stack of the directories being built.
+    pub fn from(layout: Layout, sources: &mut SourceMap, errors: &mut Errors) -> Option<Entry> {
+        let mut read = |path: Intern<Utf8PathBuf>| match sources
+            .take(&path)
+            .map_or_else(|| std::fs::read_to_string(path.as_std_path()), Ok)
+        {
+            Ok(contents) => Some(Entry::File { path, contents }),
+            Err(io_error) => {
+                errors.insert_error(Error::IO {
+                    error: io_error,
+                    action: Cow::from(format!("read the contents of the path `{}`", path)),
+                });
+
+                None
+            }
+        };
+
+        let mut stack = match layout {
+            Layout::Directory { path, entries } => vec![(path, entries.into_iter(), Vec::new())],
+            Layout::File(path) => return read(path),
+        };
+
+        while let Some((_, layouts, entries)) = stack.last_mut() {
+            match layouts.next() {
+                Some(Layout::Directory { path, entries }) => {
+                    stack.push((path, entries.into_iter(), Vec::new()))
+                }
+                Some(Layout::File(path)) => entries.extend(read(path)),
+                None => {
+                    let (path, _, entries) = stack.pop().unwrap();
+                    let entry = Entry::Directory { path, entries };
+
+                    match stack.last_mut() {
+                        Some((_, _, entries)) => entries.push(entry),
+                        None => return Some(entry),
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(all(test, feature = "fs", unix))]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    use camino::Utf8PathBuf;
+
+    use super::Layout;
+    use crate::core::error::{Error, Errors};
+    use crate::core::span::SourceMap;
+
+    #[test]
+    fn symlink_cycles_are_reported() {
+        let root = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .unwrap()
+            .join(format!("bell-symlink-cycle-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+
+        fs::create_dir_all(root.join("inner")).unwrap();
+        fs::write(root.join("main.bell"), "func main() {}\n").unwrap();
+        symlink(&root, root.join("inner").join("loop")).unwrap();
+
+        let mut errors = Errors {
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            sources: SourceMap::new(),
+        };
+        let layout = Layout::walk(root.clone(), &mut errors);
+
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(layout.is_some());
+        assert!(errors
+            .errors
+            .iter()
+            .any(|error| matches!(error, Error::ModuleCycle { .. })));
     }
 }
@@ -1,8 +1,11 @@
 use internment::Intern;
 
 pub mod ast;
+pub mod diagnostic;
 pub mod error;
+pub mod explanations;
 pub mod file;
+pub mod progress;
 pub mod span;
 pub mod token;
 pub mod types;
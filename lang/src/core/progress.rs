@@ -0,0 +1,44 @@
+use std::cell::RefCell;
+
+use camino::Utf8PathBuf;
+use internment::Intern;
+
+use super::span::Span;
+
+// What the compiler was doing, so that an internal compiler error can say where it happened. Compilations may run on
+// several threads at once, so each thread keeps track of it's own.
+#[derive(Clone)]
+pub struct Progress {
+    pub phase: &'static str,
+    pub path: Option<Intern<Utf8PathBuf>>,
+    pub span: Option<Span>,
+}
+
+thread_local! {
+    static PROGRESS: RefCell<Option<Progress>> = const { RefCell::new(None) };
+}
+
+// Starts a phase, such as "parsing", optionally of a specific file.
+pub fn enter(phase: &'static str, path: Option<Intern<Utf8PathBuf>>) {
+    PROGRESS.with(|progress| {
+        *progress.borrow_mut() = Some(Progress {
+            phase,
+            path,
+            span: None,
+        })
+    });
+}
+
+// Marks the code the current phase is working on. This is called for every expression, so it only replaces the span.
+pub fn at(span: &Span) {
+    PROGRESS.with(|progress| {
+        if let Some(progress) = progress.borrow_mut().as_mut() {
+            progress.path = Some(span.path);
+            progress.span = Some(span.clone());
+        }
+    });
+}
+
+pub fn current() -> Option<Progress> {
+    PROGRESS.with(|progress| progress.borrow().clone())
+}
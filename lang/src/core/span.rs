@@ -1,16 +1,67 @@
 use camino::Utf8PathBuf;
+#[cfg(feature = "fs")]
+use std::borrow::Cow;
 use std::collections::HashMap;
+#[cfg(feature = "fs")]
+use std::fs;
 use std::hash::Hash;
 use std::ops::Range;
 
 use internment::Intern;
+#[cfg(feature = "fs")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg(feature = "fs")]
+use super::error::Error;
+#[cfg(feature = "fs")]
+use super::file::Layout;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub struct Span {
     pub path: Intern<Utf8PathBuf>,
     pub range: Range<usize>,
+    pub origin: SpanOrigin,
+}
+
+// Desugaring generates code which was never written, such as the calls a string is made of. The spans of that code still
+// point at the closest text, but also remember the range of the expression it was generated from, so that errors in it
+// can show where it came from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SpanOrigin {
+    #[default]
+    Written,
+    Generated {
+        from: Range<usize>,
+    },
+}
+
+impl Span {
+    // Code generated from other generated code is attributed to the expression that was written.
+    pub fn generated_from(self, expression: &Span) -> Self {
+        let from = match &expression.origin {
+            SpanOrigin::Written => expression.range.clone(),
+            SpanOrigin::Generated { from } => from.clone(),
+        };
+
+        Span {
+            origin: SpanOrigin::Generated { from },
+            ..self
+        }
+    }
+}
+
+// miette can't tell apart spans of different files, so only the range is kept. Whoever renders the diagnostic must
+// attach the contents of the span's file to it.
+#[cfg(feature = "miette")]
+impl From<&Span> for miette::SourceSpan {
+    fn from(span: &Span) -> Self {
+        (span.range.start, span.range.len()).into()
+    }
 }
 
+// Sources are kept exactly as they were read, line endings included, so that spans are byte offsets into the file itself,
+// which is what anything reading the file again expects. Displaying a source must deal with a `\r` before a line break.
 pub struct SourceMap(pub HashMap<Intern<Utf8PathBuf>, String>);
 
 impl Default for SourceMap {
@@ -24,16 +75,54 @@ impl SourceMap {
         Self(HashMap::new())
     }
 
+    // Reads every Bell file in the project up front, so that the reads can happen in parallel instead of being
+    // interleaved with lexing. Anything that couldn't be read is left out, and the errors are returned along with
+    // everything that was.
+    #[cfg(feature = "fs")]
+    pub fn load_project(project: &Layout) -> (Self, Vec<Error>) {
+        let mut sources = Self::new();
+        let mut errors = Vec::new();
+
+        for source in project
+            .files()
+            .into_par_iter()
+            .map(|path| match fs::read_to_string(path.as_std_path()) {
+                Ok(contents) => Ok((path, contents)),
+                Err(error) => Err(Error::IO {
+                    error,
+                    action: Cow::from(format!("read the contents of the path `{}`", path)),
+                }),
+            })
+            .collect::<Vec<_>>()
+        {
+            match source {
+                Ok((path, contents)) => sources.insert(path, contents),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        (sources, errors)
+    }
+
     pub fn insert(&mut self, id: Intern<Utf8PathBuf>, contents: String) {
         self.0.insert(id, contents);
     }
 
+    pub fn take(&mut self, id: &Intern<Utf8PathBuf>) -> Option<String> {
+        self.0.remove(id)
+    }
+
     pub fn merge(&mut self, other: Self) {
-        self.0.extend(other.0.into_iter())
+        self.0.extend(other.0)
     }
 
     #[allow(clippy::ptr_arg)]
     pub fn get_contents(&self, id: &Utf8PathBuf) -> Option<String> {
         self.0.get(id).cloned()
     }
+
+    // The text a span covers, which is `None` if the file isn't loaded or the range doesn't fit inside of it.
+    pub fn snippet(&self, span: &Span) -> Option<&str> {
+        self.0.get(&span.path)?.get(span.range.clone())
+    }
 }
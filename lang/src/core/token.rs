@@ -4,18 +4,23 @@ use internment::Intern;
 
 use super::{span::Span, Name};
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Hash)]
 pub enum Token {
     Variable,
+    Mutable,
     Loop,
     Break,
     Continue,
     Return,
     Function,
     Structure,
+    Enumeration,
+    Match,
     If,
     Else,
     Use,
+    As,
+    Null,
     Add,
     Minus,
     Multiply,
@@ -28,12 +33,22 @@ pub enum Token {
     Equal,
     NotEqual,
     Or,
+    Pipe,
     And,
     Assign,
+    Question,
+    At,
+    AddAssign,
+    SubtractAssign,
+    MultiplyAssign,
+    DivideAssign,
+    ModuloAssign,
     Reference,
     Specify,
     Of,
+    Spread,
     Arrow,
+    FatArrow,
     ModuleAcess,
     Terminate,
     Separate,
@@ -43,19 +58,16 @@ pub enum Token {
     Left,
     Right,
     Name(Name),
+    Underscore,
+    Label(Name),
     String(Intern<String>), // I could use Name for this, but I think this more clearly conveys the meaning of the data being stored.
     Int(i32),
     Boolean(bool),
     EndOfFile,
+    #[default]
     Error,
 }
 
-impl Default for Token {
-    fn default() -> Self {
-        Self::Error
-    }
-}
-
 type StringElement = Vec<(MetaToken, Span)>;
 
 #[derive(Debug, Clone)]
@@ -69,15 +81,20 @@ impl Display for Token {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_str(match self {
             Token::Variable => "`var`",
+            Token::Mutable => "`mut`",
             Token::Loop => "`loop`",
             Token::Break => "`break`",
             Token::Continue => "`continue`",
             Token::Return => "`return`",
             Token::Function => "`func`",
             Token::Structure => "`struct`",
+            Token::Enumeration => "`enum`",
+            Token::Match => "`match`",
             Token::If => "`if`",
             Token::Else => "`else`",
             Token::Use => "`use`",
+            Token::As => "`as`",
+            Token::Null => "`null`",
             Token::Add => "`+`",
             Token::Minus => "`-`",
             Token::Multiply => "`*`",
@@ -88,12 +105,22 @@ impl Display for Token {
             Token::Equal => "`==`",
             Token::NotEqual => "`!=`",
             Token::Or => "`||`",
+            Token::Pipe => "`|`",
             Token::And => "`&&`",
             Token::Assign => "`=`",
+            Token::Question => "`?`",
+            Token::At => "`@`",
+            Token::AddAssign => "`+=`",
+            Token::SubtractAssign => "`-=`",
+            Token::MultiplyAssign => "`*=`",
+            Token::DivideAssign => "`/=`",
+            Token::ModuloAssign => "`%=`",
             Token::Reference => "`&`",
             Token::Specify => "`:`",
             Token::Of => "`.`",
+            Token::Spread => "`..`",
             Token::Arrow => "`->`",
+            Token::FatArrow => "`=>`",
             Token::ModuleAcess => "`::`",
             Token::Terminate => "`;`",
             Token::Lesser => "`<`",
@@ -108,6 +135,8 @@ impl Display for Token {
             Token::Boolean(_) => "boolean",
             Token::String(_) => "string",
             Token::Name(_) => "identifier",
+            Token::Underscore => "`_`",
+            Token::Label(_) => "label",
             Token::EndOfFile => "end of file",
             _ => unreachable!(),
         })
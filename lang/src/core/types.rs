@@ -1,16 +1,30 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::HashMap, fmt::Display, fmt::Write};
 
 use super::{
-    ast::{self, Id},
+    ast::{self, Id, IntegerWidth},
+    error::{Element, Error, Errors},
     span::Span,
     Name,
 };
 
 pub type TypeId = usize;
 
+// Why a type isn't known yet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unknown {
+    // A hole to be filled in by inference.
+    Inferable,
+    // The type of something that already had an error reported for it. It unifies with anything without learning from
+    // it, so that the error doesn't cascade into more errors.
+    Errored,
+    // An integer literal, which becomes whichever integer type it is used as, and an `Int` if it's never used as one.
+    // Whether it's value fits in that type is checked along with the constant.
+    Integer,
+}
+
 #[derive(Clone, Debug)]
 pub enum TypeInfo {
-    Unknown(bool),
+    Unknown(Unknown),
     Reference(TypeId),
     // A link physically links two types together, all information discovered on one is discovered on another.
     // It's different than references, since those are talking about actual object references.
@@ -20,10 +34,23 @@ pub enum TypeInfo {
     },
     Unit,
     Integer,
+    RangedInteger {
+        min: i32,
+        max: i32,
+    },
+    SizedInteger(IntegerWidth),
     Boolean,
     String,
     Structure(HashMap<Name, TypeId>),
-    Instance(TypeId),
+    // Maps each variant to the types of it's payload.
+    Enumeration(HashMap<Name, Vec<TypeId>>),
+    // The origin is where the instance was constructed, if it's known.
+    Instance {
+        structure: TypeId,
+        origin: Option<Span>,
+    },
+    // A value that is either of the inner type or null.
+    Optional(TypeId),
     Function {
         parameters: Vec<TypeId>,
         return_type: TypeId,
@@ -31,36 +58,53 @@ pub enum TypeInfo {
 }
 
 pub trait IntoTyInfo {
-    fn into_ty(self, scopes: &Scopes) -> TypeInfo;
+    fn into_ty(self, scopes: &StaticScopes, engine: &mut Engine, errors: &mut Errors) -> TypeInfo;
 }
 
-impl IntoTyInfo for ast::Type {
-    fn into_ty(self, scopes: &Scopes) -> TypeInfo {
-        match self {
+// Types inside of other ones, such as the inner type of an optional, are given the span of the whole type hint.
+impl IntoTyInfo for (ast::Type, Span) {
+    fn into_ty(self, scopes: &StaticScopes, engine: &mut Engine, errors: &mut Errors) -> TypeInfo {
+        let (data_type, span) = self;
+
+        match data_type {
             ast::Type::Integer => TypeInfo::Integer,
+            ast::Type::RangedInteger { min, max } => TypeInfo::RangedInteger { min, max },
+            ast::Type::SizedInteger(width) => TypeInfo::SizedInteger(width),
             ast::Type::Boolean => TypeInfo::Boolean,
             ast::Type::String => TypeInfo::String,
             ast::Type::Structure(id) => {
-                // TODO: Refactor this.
-                if let Some(&Variable { type_id, .. }) =
-                    scopes.raw_scopes.search_id(&id, scopes.current)
-                {
-                    TypeInfo::Instance(type_id)
+                if let Some(&Variable { type_id, .. }) = scopes.search_id(&id) {
+                    TypeInfo::Instance {
+                        structure: type_id,
+                        origin: None,
+                    }
                 } else {
-                    // No need to report this as an error, this should ALWAYS be reported.
-                    // TODO: Could be wrong! Please verify this!
-                    TypeInfo::Unknown(true)
+                    // Nothing else resolves the names in type hints, so the structure is reported as missing here.
+                    errors.insert_error(Error::MissingId {
+                        id: Element { value: id, span },
+                    });
+
+                    TypeInfo::Unknown(Unknown::Errored)
                 }
             }
-            ast::Type::Reference(data_type) => data_type.into_ty(scopes),
+            ast::Type::Reference(data_type) => {
+                let inner = (*data_type, span).into_ty(scopes, engine, errors);
+
+                TypeInfo::Reference(engine.insert_type(inner, None))
+            }
+            ast::Type::Optional(data_type) => {
+                let inner = (*data_type, span).into_ty(scopes, engine, errors);
+
+                TypeInfo::Optional(engine.insert_type(inner, None))
+            }
         }
     }
 }
 
 impl IntoTyInfo for Option<(ast::Type, Span)> {
-    fn into_ty(self, scopes: &Scopes) -> TypeInfo {
-        self.map(|(type_hint, _)| type_hint.into_ty(scopes))
-            .unwrap_or(TypeInfo::Unknown(false))
+    fn into_ty(self, scopes: &StaticScopes, engine: &mut Engine, errors: &mut Errors) -> TypeInfo {
+        self.map(|type_hint| type_hint.into_ty(scopes, engine, errors))
+            .unwrap_or(TypeInfo::Unknown(Unknown::Inferable))
     }
 }
 
@@ -73,26 +117,36 @@ pub struct Mismatch {
 
 pub type Types = Vec<(TypeInfo, Option<Span>)>;
 
+#[derive(Default)]
 pub struct Engine {
     pub types: Types,
     pub mismatches: Vec<Mismatch>,
+    // An unknown, and the type it wasn't linked to because the type contains it.
+    pub infinite: Vec<(TypeId, TypeId)>,
 }
 
 impl Engine {
     pub fn new() -> Self {
-        Self {
-            types: Vec::new(),
-            mismatches: Vec::new(),
-        }
+        Self::default()
     }
 
     pub fn new_with_types(types: Types) -> Self {
         Self {
             types,
             mismatches: Vec::new(),
+            infinite: Vec::new(),
         }
     }
 
+    // Whether the types would unify, without linking them. It unifies a copy of the types, so it's only meant for
+    // explaining errors.
+    pub fn unifies(&self, a: TypeId, b: TypeId) -> bool {
+        let mut engine = Self::new_with_types(self.types.clone());
+        engine.unify(a, b, LinkReason::Other);
+
+        engine.mismatches.is_empty() && engine.infinite.is_empty()
+    }
+
     pub fn get(&self, type_id: TypeId) -> &(TypeInfo, Option<Span>) {
         &self.types[type_id]
     }
@@ -104,10 +158,139 @@ impl Engine {
         id
     }
 
-    pub fn remove_ref(&self, type_info: TypeInfo) -> TypeInfo {
-        match type_info {
-            TypeInfo::Link { linked_to, .. } => self.remove_ref(self.types[linked_to].0.clone()),
-            _ => type_info,
+    // Follows the links of a type to the type it's ultimately linked to.
+    pub fn resolve(&self, mut type_id: TypeId) -> TypeId {
+        while let TypeInfo::Link { linked_to, .. } = self.types[type_id].0 {
+            type_id = linked_to;
+        }
+
+        type_id
+    }
+
+    // The type a type is ultimately linked to. This borrows the type instead of cloning it, so only the parts of it that
+    // are needed have to be copied out.
+    pub fn get_resolved(&self, type_id: TypeId) -> &TypeInfo {
+        &self.types[self.resolve(type_id)].0
+    }
+
+    // The type a type stands for once it's links are followed. The engine doesn't know the names of structures, so they
+    // are looked up using `name_of`, and instances of structures it has no name for are unknown.
+    pub fn into_concrete_ty(
+        &self,
+        type_id: TypeId,
+        name_of: &impl Fn(TypeId) -> Option<Name>,
+    ) -> Type {
+        match self.get_resolved(type_id) {
+            TypeInfo::Unknown(Unknown::Integer) => Type::Integer,
+            TypeInfo::Unknown(_) => Type::Unknown,
+            &TypeInfo::Reference(type_id) => {
+                Type::Reference(Box::new(self.into_concrete_ty(type_id, name_of)))
+            }
+            TypeInfo::Link { .. } => unreachable!(),
+            TypeInfo::Unit => Type::Unit,
+            TypeInfo::Integer => Type::Integer,
+            &TypeInfo::RangedInteger { min, max } => Type::RangedInteger { min, max },
+            &TypeInfo::SizedInteger(width) => Type::SizedInteger(width),
+            TypeInfo::Boolean => Type::Boolean,
+            TypeInfo::String => Type::String,
+            TypeInfo::Structure(fields) => Type::Structure(Fields::new(
+                fields
+                    .iter()
+                    .map(|(&name, &type_id)| (name, self.into_concrete_ty(type_id, name_of)))
+                    .collect(),
+            )),
+            TypeInfo::Enumeration(variants) => Type::Enumeration(Variants::new(
+                variants
+                    .iter()
+                    .map(|(&name, payload)| {
+                        (
+                            name,
+                            payload
+                                .iter()
+                                .map(|&type_id| self.into_concrete_ty(type_id, name_of))
+                                .collect(),
+                        )
+                    })
+                    .collect(),
+            )),
+            &TypeInfo::Instance {
+                structure: type_id, ..
+            } => {
+                name_of(type_id).map_or(Type::Unknown, |name| Type::Instance(Id::new_single(name)))
+            }
+            &TypeInfo::Optional(type_id) => {
+                Type::Optional(Box::new(self.into_concrete_ty(type_id, name_of)))
+            }
+            &TypeInfo::Function {
+                ref parameters,
+                return_type,
+            } => Type::Function {
+                parameters: parameters
+                    .iter()
+                    .map(|&type_id| self.into_concrete_ty(type_id, name_of))
+                    .collect(),
+                return_type: Box::new(self.into_concrete_ty(return_type, name_of)),
+            },
+        }
+    }
+
+    // Whether the unknown `variable` appears anywhere inside of a type. Linking the unknown to such a type would make it
+    // contain itself, so following it's links would never end. Instances are named, so they can refer to themselves
+    // without being infinite.
+    pub fn occurs_check(&self, variable: TypeId, type_id: TypeId) -> bool {
+        let variable = self.resolve(variable);
+        let mut stack = vec![type_id];
+
+        while let Some(type_id) = stack.pop() {
+            let type_id = self.resolve(type_id);
+
+            if type_id == variable {
+                return true;
+            }
+
+            match &self.types[type_id].0 {
+                &TypeInfo::Reference(inner) | &TypeInfo::Optional(inner) => stack.push(inner),
+                TypeInfo::Structure(fields) => stack.extend(fields.values().copied()),
+                TypeInfo::Enumeration(variants) => {
+                    stack.extend(variants.values().flatten().copied())
+                }
+                TypeInfo::Function {
+                    parameters,
+                    return_type,
+                } => {
+                    stack.extend(parameters.iter().copied());
+                    stack.push(*return_type);
+                }
+                _ => (),
+            }
+        }
+
+        false
+    }
+
+    // Unlike unification this is directional, a value of type `from` may be used where an optional of it is expected.
+    // Any other case is left to widening, meaning an optional can't be used where it's inner type is expected.
+    pub fn coerce(&mut self, from: TypeId, to: TypeId, reason: LinkReason) {
+        match (self.get_resolved(from), self.get_resolved(to)) {
+            (TypeInfo::Optional(_) | TypeInfo::Unknown(_), _) => self.unify(from, to, reason),
+            (_, &TypeInfo::Optional(inner)) => self.widen(from, inner, reason),
+            _ => self.widen(from, to, reason),
+        }
+    }
+
+    // A narrower integer may be used where a wider one is expected, but not the other way around, since whether a value
+    // fits in a narrower range is only known for constants. Any other case is left to unification.
+    pub fn widen(&mut self, from: TypeId, to: TypeId, reason: LinkReason) {
+        match (self.get_resolved(from), self.get_resolved(to)) {
+            (
+                &TypeInfo::RangedInteger { min, max },
+                &TypeInfo::RangedInteger {
+                    min: to_min,
+                    max: to_max,
+                },
+            ) if min >= to_min && max <= to_max => (),
+            (TypeInfo::RangedInteger { .. }, TypeInfo::Integer) => (),
+            _ => self.unify(from, to, reason),
         }
     }
 
@@ -116,46 +299,147 @@ impl Engine {
     }
 
     fn unify_with_context(&mut self, a: TypeId, b: TypeId, context: UnifyCtx) {
-        match (self.types[a].0.clone(), self.types[b].0.clone()) {
-            // Overwrite unknowns.
-            (TypeInfo::Unknown { .. }, _) => {
+        // Both sides are followed to the types they are linked to first, so that an unknown behind a link is linked or
+        // poisoned itself, rather than the link to it.
+        let (a, b) = (self.resolve(a), self.resolve(b));
+
+        // Types which are already linked to the same type are equal. Linking an unknown to a type that links back to it
+        // would instead make a cycle of links, which nothing following the links would ever get out of.
+        if a == b {
+            return;
+        }
+
+        // Nested types are only copied out as the ids they are made of, so that they can be unified once the types are no
+        // longer borrowed.
+        match (&self.types[a].0, &self.types[b].0) {
+            // An errored type poisons the unknowns it is unified with, so that they don't learn a type from their next use
+            // and mismatch with the one after it. Anything else is left as is, and never mismatches with it.
+            (TypeInfo::Unknown(Unknown::Inferable), TypeInfo::Unknown(Unknown::Errored)) => {
                 self.types[a].0 = TypeInfo::Link {
                     linked_to: b,
                     reason: context.reason,
                 }
             }
-            (_, TypeInfo::Unknown { .. }) => {
+            (TypeInfo::Unknown(Unknown::Errored), TypeInfo::Unknown(Unknown::Inferable)) => {
+                self.types[b].0 = TypeInfo::Link {
+                    linked_to: a,
+                    reason: context.reason,
+                }
+            }
+            (TypeInfo::Unknown(Unknown::Errored), _) | (_, TypeInfo::Unknown(Unknown::Errored)) => {}
+
+            // Overwrite unknowns, unless the type they would be linked to contains them.
+            (TypeInfo::Unknown(Unknown::Inferable), _) if self.occurs_check(a, b) => {
+                self.infinite.push((a, b))
+            }
+            (_, TypeInfo::Unknown(Unknown::Inferable)) if self.occurs_check(b, a) => {
+                self.infinite.push((b, a))
+            }
+            (TypeInfo::Unknown(Unknown::Inferable), _) => {
+                self.types[a].0 = TypeInfo::Link {
+                    linked_to: b,
+                    reason: context.reason,
+                }
+            }
+            (_, TypeInfo::Unknown(Unknown::Inferable)) => {
+                self.types[b].0 = TypeInfo::Link {
+                    linked_to: a,
+                    reason: context.reason,
+                }
+            }
+            // Integer literals only ever learn integer types.
+            (
+                TypeInfo::Unknown(Unknown::Integer),
+                TypeInfo::Unknown(Unknown::Integer)
+                | TypeInfo::Integer
+                | TypeInfo::RangedInteger { .. }
+                | TypeInfo::SizedInteger(_),
+            ) => {
+                self.types[a].0 = TypeInfo::Link {
+                    linked_to: b,
+                    reason: context.reason,
+                }
+            }
+            (
+                TypeInfo::Integer | TypeInfo::RangedInteger { .. } | TypeInfo::SizedInteger(_),
+                TypeInfo::Unknown(Unknown::Integer),
+            ) => {
                 self.types[b].0 = TypeInfo::Link {
                     linked_to: a,
                     reason: context.reason,
                 }
             }
 
-            // Follow any links.
-            (TypeInfo::Link { linked_to, .. }, _) => self.unify_with_context(linked_to, b, context),
-            (_, TypeInfo::Link { linked_to, .. }) => self.unify_with_context(a, linked_to, context),
             (TypeInfo::Integer, TypeInfo::Integer) => (),
+            // Widths differing from each other, from `Int`, or from a range, need an explicit conversion such as `to_i8`.
+            (TypeInfo::SizedInteger(a), TypeInfo::SizedInteger(b)) if a == b => (),
+            // Unification can't tell which side flows into the other, so only equal ranges unify. A narrower range is
+            // used where a wider one is expected by widening.
+            (
+                &TypeInfo::RangedInteger { min, max },
+                &TypeInfo::RangedInteger {
+                    min: other_min,
+                    max: other_max,
+                },
+            ) if min == other_min && max == other_max => (),
             (TypeInfo::Boolean, TypeInfo::Boolean) => (),
             (TypeInfo::String, TypeInfo::String) => (),
-            (TypeInfo::Reference(a), TypeInfo::Reference(b)) => {
+            (&TypeInfo::Reference(a), &TypeInfo::Reference(b)) => {
+                self.unify_with_context(a, b, context)
+            }
+            (&TypeInfo::Optional(a), &TypeInfo::Optional(b)) => {
                 self.unify_with_context(a, b, context)
             }
 
             // All identifiers at this point are absolute, so this code holds.
-            (TypeInfo::Instance(id), TypeInfo::Instance(other)) if id == other => (),
+            (
+                TypeInfo::Instance { structure: id, .. },
+                TypeInfo::Instance {
+                    structure: other, ..
+                },
+            ) if id == other => (),
             (TypeInfo::Structure(fields_a), TypeInfo::Structure(fields_b)) => {
-                for (field, data_type) in fields_a {
-                    if let Some(&other) = fields_b.get(&field) {
-                        self.unify(data_type, other, LinkReason::Field);
-                    } else {
-                        self.mismatches.push(Mismatch {
-                            a: context.a,
-                            b: context.b,
-                            reason: context.reason,
-                        });
-
-                        return;
+                let fields = fields_a
+                    .iter()
+                    .map(|(field, &data_type)| fields_b.get(field).map(|&other| (data_type, other)))
+                    .collect::<Option<Vec<_>>>();
+
+                match fields {
+                    Some(fields) => {
+                        for (a, b) in fields {
+                            self.unify(a, b, LinkReason::Field);
+                        }
+                    }
+                    None => self.mismatches.push(Mismatch {
+                        a: context.a,
+                        b: context.b,
+                        reason: context.reason,
+                    }),
+                }
+            }
+            (TypeInfo::Enumeration(variants_a), TypeInfo::Enumeration(variants_b)) => {
+                let payloads = variants_a
+                    .iter()
+                    .map(|(variant, payload)| match variants_b.get(variant) {
+                        Some(other) if payload.len() == other.len() => {
+                            Some(payload.iter().copied().zip(other.iter().copied()))
+                        }
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .map(|payloads| payloads.into_iter().flatten().collect::<Vec<_>>());
+
+                match payloads {
+                    Some(payloads) => {
+                        for (a, b) in payloads {
+                            self.unify(a, b, LinkReason::Pattern);
+                        }
                     }
+                    None => self.mismatches.push(Mismatch {
+                        a: context.a,
+                        b: context.b,
+                        reason: context.reason,
+                    }),
                 }
             }
             (
@@ -168,7 +452,14 @@ impl Engine {
                     return_type: b_return_type,
                 },
             ) if a_parameters.len() == b_parameters.len() => {
-                for (a, b) in a_parameters.into_iter().zip(b_parameters) {
+                let parameters = a_parameters
+                    .iter()
+                    .copied()
+                    .zip(b_parameters.iter().copied())
+                    .collect::<Vec<_>>();
+                let (a_return_type, b_return_type) = (*a_return_type, *b_return_type);
+
+                for (a, b) in parameters {
                     self.unify(a, b, LinkReason::Parameter);
                 }
 
@@ -183,21 +474,31 @@ impl Engine {
     }
 }
 
-#[derive(Debug)]
-pub struct Fields(HashMap<Id, Type>);
+// Hash maps compare by their contents, so two structures are equal regardless of the order their fields were inserted in.
+#[derive(Debug, PartialEq)]
+pub struct Fields(HashMap<Name, Type>);
 
 impl Fields {
-    pub fn new(fields: HashMap<Id, Type>) -> Self {
+    pub fn new(fields: HashMap<Name, Type>) -> Self {
         Self(fields)
     }
 }
 
 impl Display for Fields {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut fields = self
+            .0
+            .iter()
+            .map(|(field, data_type)| (field.to_string(), data_type))
+            .collect::<Vec<_>>();
+
+        // Hash maps iterate in an arbitrary order, so we sort the fields by name to keep error messages stable.
+        fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+
         f.write_fmt(format_args!(
             "{{{}}}",
-            self.0
-                .iter()
+            fields
+                .into_iter()
                 .map(|(field, data_type)| format!("{}: {}", field, data_type))
                 .collect::<Vec<_>>()
                 .join(", ")
@@ -205,16 +506,61 @@ impl Display for Fields {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
+pub struct Variants(HashMap<Name, Vec<Type>>);
+
+impl Variants {
+    pub fn new(variants: HashMap<Name, Vec<Type>>) -> Self {
+        Self(variants)
+    }
+}
+
+impl Display for Variants {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut variants = self
+            .0
+            .iter()
+            .map(|(variant, payload)| {
+                if payload.is_empty() {
+                    variant.to_string()
+                } else {
+                    format!(
+                        "{}({})",
+                        variant,
+                        payload
+                            .iter()
+                            .map(|data_type| data_type.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Like with fields, the variants are sorted to keep error messages stable.
+        variants.sort();
+
+        f.write_fmt(format_args!("enum {{{}}}", variants.join(", ")))
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub enum Type {
     Unknown,
     Reference(Box<Type>),
     Unit,
     Integer,
+    RangedInteger {
+        min: i32,
+        max: i32,
+    },
+    SizedInteger(IntegerWidth),
     Boolean,
     String,
     Structure(Fields),
+    Enumeration(Variants),
     Instance(Id),
+    Optional(Box<Type>),
     Function {
         parameters: Vec<Type>,
         return_type: Box<Type>,
@@ -228,10 +574,14 @@ impl Display for Type {
             Self::Reference(data_type) => write!(f, "&{}", data_type),
             Self::Unit => write!(f, "Unit"),
             Self::Integer => write!(f, "Int"),
+            Self::RangedInteger { min, max } => write!(f, "Int<{}, {}>", min, max),
+            Self::SizedInteger(width) => write!(f, "{}", width),
             Self::Boolean => write!(f, "Bool"),
             Self::String => write!(f, "Str"),
             Self::Structure(fields) => write!(f, "{}", fields),
+            Self::Enumeration(variants) => write!(f, "{}", variants),
             Self::Instance(id) => write!(f, "{}", id),
+            Self::Optional(data_type) => write!(f, "{}?", data_type),
             Self::Function {
                 parameters,
                 return_type,
@@ -258,18 +608,26 @@ pub enum LinkReason {
     Condition,
     Field,
     Conditional,
+    Match,
+    Pattern,
+    Binding,
+    Dereference,
     Loop,
     Other,
     Parameter,
     Return,
 }
 
-impl Into<&'static str> for LinkReason {
-    fn into(self) -> &'static str {
+impl From<LinkReason> for &'static str {
+    fn from(reason: LinkReason) -> Self {
         // TODO: Confirm these sentences accurately describe the reason in an error message.
-        match self {
+        match reason {
             LinkReason::Assign => "it's being assigned to this identifier",
             LinkReason::Conditional => "all branches of a conditional must return the same type",
+            LinkReason::Match => "all arms of a match must return the same type",
+            LinkReason::Pattern => "it's matched against this pattern",
+            LinkReason::Binding => "it's bound by this conditional",
+            LinkReason::Dereference => "it's dereferenced here",
             LinkReason::Field => "it's being assigned to the field before it",
             LinkReason::Loop => "it's broken out of the loop",
             LinkReason::Return => "it's being returned from this function",
@@ -288,18 +646,36 @@ pub struct UnifyCtx {
     pub b: TypeId,
 }
 
-// For now all constraints are field constraints, I don't really know if I'll need more
-// constraints for anything else, but for now this is fine.
-#[derive(Clone, Copy, Debug)]
-pub struct Constraint {
-    pub object_id: TypeId,
-    pub field_id: TypeId,
-    pub field: Name,
+// Constraints are solved once the type of the object they depend on is known.
+#[derive(Clone, Debug)]
+pub enum Constraint {
+    Field {
+        object_id: TypeId,
+        field_id: TypeId,
+        field: Name,
+    },
+    // A method call is unified as `field_call` if the receiver has the method as a field,
+    // and otherwise as `free_call` with the function that was in scope at the call, if there was one.
+    Method {
+        receiver_id: TypeId,
+        method: Name,
+        field_call: TypeId,
+        free_call: TypeId,
+        free_function: Option<TypeId>,
+    },
+    // The fields given to an instance, along with the span of their names, are checked against the fields of it's
+    // structure. The object is the structure as it was written, and the span is the whole instance.
+    Instance {
+        object: Id,
+        structure: TypeId,
+        fields: Vec<(Name, TypeId, Span)>,
+        span: Span,
+    },
 }
 
 impl Constraint {
     pub fn new(object_id: TypeId, field_id: TypeId, field: Name) -> Self {
-        Self {
+        Self::Field {
             object_id,
             field_id,
             field,
@@ -313,6 +689,8 @@ pub type ScopeId = usize;
 pub struct Variable {
     pub type_id: TypeId,
     pub shadowable: bool,
+    // Only variables declared with `var mut` may be assigned to. Anything else, such as a parameter or a function, may not.
+    pub mutable: bool,
 }
 
 type Variables = Vec<(Name, Variable)>;
@@ -399,7 +777,7 @@ impl Scope {
 }
 
 #[derive(Debug, Clone)]
-struct RawScopes(Vec<Scope>);
+pub struct RawScopes(Vec<Scope>);
 
 impl RawScopes {
     // This will search for a module within the current inclusive scopes (In practice, the current file).
@@ -417,13 +795,13 @@ impl RawScopes {
     }
 
     pub fn does_already_exist(&self, name: Name, starting_scope: ScopeId) -> bool {
-        self.search_variable(name, starting_scope).is_some()
+        self.search_local_variable(name, starting_scope).is_some()
             || self.search_local_module(name, starting_scope).is_some()
     }
 
     pub fn get_id_origin_module(&self, id: &Id, mut current_module: ScopeId) -> Option<ScopeId> {
         // This code will explore the non-tail parts of the ID and eventually locate the origin module of that ID.
-        for id_part in id.0[..id.0.len() - 1].iter().copied() {
+        for id_part in id.parent().iter().copied() {
             if let Some(scope_id) = self.search_module(id_part, current_module) {
                 current_module = scope_id;
             } else {
@@ -438,24 +816,25 @@ impl RawScopes {
 
     // This code searchs for an ID that ultimately refers to a variable, not another module.
     pub fn search_id(&self, id: &Id, current_module: ScopeId) -> Option<&Variable> {
-        let id_tail = *id.0.last().unwrap(); // We assume the ID refers to a variable, so the last part of the ID naturally is that variable name.
+        let id_tail = id.leaf(); // We assume the ID refers to a variable, so the last part of the ID naturally is that variable name.
 
         self.get_id_origin_module(id, current_module)
-            .and_then(|origin_module| {
-                if let Some(variable) = self.search_variable(id_tail, origin_module) {
-                    Some(variable)
-                } else {
-                    None
-                }
-            })
+            .and_then(|origin_module| self.search_variable(id_tail, origin_module))
     }
 
+    // The root scope holds the items of the prelude, which are visible from every module. They are searched last, so
+    // any item of the module shadows them.
     pub fn search_variable(&self, name: Name, starting_scope: ScopeId) -> Option<&Variable> {
+        self.search_local_variable(name, starting_scope)
+            .or_else(|| self.0[0].search_variable(name))
+    }
+
+    fn search_local_variable(&self, name: Name, starting_scope: ScopeId) -> Option<&Variable> {
         let scope = &self.0[starting_scope];
 
         scope.search_variable(name).or_else(|| {
             if let ScopeConnection::Inclusive(scope_id) = scope.connection {
-                self.search_variable(name, scope_id)
+                self.search_local_variable(name, scope_id)
             } else {
                 None
             }
@@ -479,6 +858,81 @@ impl RawScopes {
             .iter()
             .find_map(|scope| scope.search_name_by_type(type_id))
     }
+
+    // Prints every scope under the scope it's connected to, along with the modules and variables in it. This is only
+    // meant for debugging how names are resolved.
+    pub fn dump(&self, current: ScopeId) -> String {
+        let mut children = vec![Vec::new(); self.0.len()];
+        let mut roots = Vec::new();
+
+        for (scope_id, scope) in self.0.iter().enumerate() {
+            match scope.connection.as_option() {
+                Some(parent) => children[parent].push(scope_id),
+                None => roots.push(scope_id),
+            }
+        }
+
+        // Like walking directories, this uses an explicit stack so that deeply nested blocks can't overflow the stack.
+        let mut stack = roots
+            .into_iter()
+            .rev()
+            .map(|scope_id| (scope_id, 0))
+            .collect::<Vec<_>>();
+        let mut output = String::new();
+
+        while let Some((scope_id, depth)) = stack.pop() {
+            let scope = &self.0[scope_id];
+            let indent = "    ".repeat(depth);
+
+            writeln!(
+                output,
+                "{}#{} {}{}",
+                indent,
+                scope_id,
+                match scope.connection {
+                    ScopeConnection::Inclusive(_) => "inclusive",
+                    ScopeConnection::Exclusive(_) => "exclusive",
+                    ScopeConnection::None => "root",
+                },
+                if scope_id == current {
+                    " (current)"
+                } else {
+                    ""
+                }
+            )
+            .unwrap();
+
+            for (name, module) in &scope.modules {
+                writeln!(output, "{}    module {} -> #{}", indent, name, module).unwrap();
+            }
+
+            for (name, variable) in &scope.variables {
+                writeln!(
+                    output,
+                    "{}    {}{}: type #{}{}",
+                    indent,
+                    if variable.mutable { "mut " } else { "" },
+                    name,
+                    variable.type_id,
+                    if variable.shadowable {
+                        ""
+                    } else {
+                        " (not shadowable)"
+                    }
+                )
+                .unwrap();
+            }
+
+            stack.extend(
+                children[scope_id]
+                    .iter()
+                    .rev()
+                    .map(|&child| (child, depth + 1)),
+            );
+        }
+
+        output
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -487,18 +941,28 @@ pub struct Scopes {
     pub current: ScopeId,
 }
 
-impl Scopes {
-    pub fn new() -> Self {
+impl Default for Scopes {
+    fn default() -> Self {
         Scopes {
             raw_scopes: RawScopes(vec![Scope::new(ScopeConnection::None)]),
             current: 0,
         }
     }
+}
+
+impl Scopes {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
     pub fn does_already_exist(&self, name: Name) -> bool {
         self.raw_scopes.does_already_exist(name, self.current)
     }
 
+    pub fn dump(&self) -> String {
+        self.raw_scopes.dump(self.current)
+    }
+
     pub fn new_with_scopes(raw_scopes: RawScopes) -> Self {
         Self {
             raw_scopes,
@@ -519,6 +983,11 @@ impl Scopes {
         self.current
     }
 
+    // Functions and blocks see everything in the scope they are in.
+    pub fn enter_scope(&mut self) {
+        self.create_scope(ScopeConnection::Inclusive(self.current));
+    }
+
     pub fn exit_current_scope(&mut self) {
         self.current = self.raw_scopes.0[self.current]
             .connection
@@ -548,7 +1017,7 @@ impl Scopes {
     }
 
     // Type ids are unique so we search all scopes.
-    fn search_name_by_type(&self, type_id: TypeId) -> Option<Name> {
+    pub fn search_name_by_type(&self, type_id: TypeId) -> Option<Name> {
         self.raw_scopes.search_name_by_type(type_id)
     }
 }
@@ -608,8 +1077,11 @@ impl StaticScopes {
         self.raw_scopes.0[self.current].insert_module(name, scope_id);
     }
 
+    // Values bound to `_` are discarded, so they are never inserted and can't be referenced.
     pub fn insert_variable(&mut self, name: Name, variable: Variable) {
-        self.raw_scopes.0[self.current].insert_variable(name, variable);
+        if name.as_str() != "_" {
+            self.raw_scopes.0[self.current].insert_variable(name, variable);
+        }
     }
 
     pub fn search_id(&self, id: &Id) -> Option<&Variable> {
@@ -624,8 +1096,13 @@ impl StaticScopes {
         self.raw_scopes.search_module(name, self.current)
     }
 
+    // Like `search_variable`, but the root scope, which is where the prelude is, isn't searched.
+    pub fn search_local_variable(&self, name: Name) -> Option<&Variable> {
+        self.raw_scopes.search_local_variable(name, self.current)
+    }
+
     // Type ids are unique so we search all scopes.
-    fn search_type_name_by_id(&self, type_id: TypeId) -> Option<Name> {
+    pub fn search_type_name_by_id(&self, type_id: TypeId) -> Option<Name> {
         self.raw_scopes.search_name_by_type(type_id)
     }
 }
@@ -651,3 +1128,124 @@ impl ScopeContext {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use internment::Intern;
+
+    use super::{Engine, LinkReason, TypeId, TypeInfo, Unknown};
+    use crate::core::ast::IntegerWidth;
+
+    // A xorshift generator, so that the harness is reproducible without depending on a crate for randomness.
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+
+            (self.0 % bound as u64) as usize
+        }
+    }
+
+    // Every type it contains already exists, so types only become cyclic through unification.
+    fn random_type(random: &mut Random, existing: usize) -> TypeInfo {
+        let mut child = || random.below(existing);
+        let names = ["a", "b", "c"];
+
+        match child() % 13 {
+            0 => TypeInfo::Unknown(Unknown::Inferable),
+            1 => TypeInfo::Unknown(Unknown::Errored),
+            2 => TypeInfo::Unknown(Unknown::Integer),
+            3 => TypeInfo::Reference(child()),
+            4 => TypeInfo::Unit,
+            5 => TypeInfo::Integer,
+            6 => TypeInfo::RangedInteger { min: 0, max: 10 },
+            7 => TypeInfo::SizedInteger(IntegerWidth::I8),
+            8 => TypeInfo::Boolean,
+            9 => TypeInfo::Structure(
+                names
+                    .iter()
+                    .map(|name| (Intern::new(name.to_string()), child()))
+                    .collect::<HashMap<_, _>>(),
+            ),
+            10 => TypeInfo::Instance {
+                structure: child(),
+                origin: None,
+            },
+            11 => TypeInfo::Optional(child()),
+            _ => TypeInfo::Function {
+                parameters: vec![child(), child()],
+                return_type: child(),
+            },
+        }
+    }
+
+    #[test]
+    fn concrete_types_of_random_graphs_terminate() {
+        for seed in 1..=256 {
+            let mut random = Random(seed);
+            let mut engine = Engine::new();
+
+            engine.insert_type(TypeInfo::Unknown(Unknown::Inferable), None);
+
+            for _ in 0..32 {
+                let data_type = random_type(&mut random, engine.types.len());
+                engine.insert_type(data_type, None);
+            }
+
+            for _ in 0..32 {
+                let (a, b): (TypeId, TypeId) = (
+                    random.below(engine.types.len()),
+                    random.below(engine.types.len()),
+                );
+
+                match random.below(3) {
+                    0 => engine.unify(a, b, LinkReason::Other),
+                    1 => engine.coerce(a, b, LinkReason::Other),
+                    _ => engine.widen(a, b, LinkReason::Other),
+                }
+            }
+
+            for type_id in 0..engine.types.len() {
+                engine.into_concrete_ty(type_id, &|_| None);
+            }
+        }
+    }
+
+    #[test]
+    fn unknowns_never_contain_themselves() {
+        let mut engine = Engine::new();
+
+        let unknown = engine.insert_type(TypeInfo::Unknown(Unknown::Inferable), None);
+        let reference = engine.insert_type(TypeInfo::Reference(unknown), None);
+        engine.unify(unknown, reference, LinkReason::Other);
+
+        let function = engine.insert_type(TypeInfo::Unknown(Unknown::Inferable), None);
+        let unit = engine.insert_type(TypeInfo::Unit, None);
+        let signature = engine.insert_type(
+            TypeInfo::Function {
+                parameters: vec![function],
+                return_type: unit,
+            },
+            None,
+        );
+        engine.unify(signature, function, LinkReason::Other);
+
+        assert_eq!(
+            engine.infinite,
+            vec![(unknown, reference), (function, signature)]
+        );
+        assert!(matches!(
+            engine.get_resolved(unknown),
+            TypeInfo::Unknown(Unknown::Inferable)
+        ));
+
+        // Both are still unknown, so they can be turned into concrete types without looping.
+        engine.into_concrete_ty(reference, &|_| None);
+        engine.into_concrete_ty(signature, &|_| None);
+    }
+}
@@ -0,0 +1,71 @@
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::core::ast::Expression;
+use crate::core::span::Span;
+use crate::VERSION;
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry<'a> {
+    // The AST may change between versions of the compiler, so an entry is only used by the version that wrote it.
+    version: Cow<'a, str>,
+    path: Cow<'a, str>,
+    // The whole file is stored, and not just it's hash, so that a hash collision can never cause a stale AST to be used.
+    contents: Cow<'a, str>,
+    ast: Cow<'a, [(Expression, Span)]>,
+}
+
+// An on-disk cache of the ASTs of files, so that files that didn't change since the last compilation aren't lexed and
+// parsed again. The cache is only an optimization, so failing to read or write it is the same as a cache miss.
+pub struct AstCache {
+    directory: Utf8PathBuf,
+}
+
+impl AstCache {
+    pub fn new(directory: Utf8PathBuf) -> Self {
+        Self { directory }
+    }
+
+    fn entry_path(&self, path: &Utf8Path, contents: &str) -> Utf8PathBuf {
+        let mut hasher = DefaultHasher::new();
+
+        // The spans in an AST contain the path of the file, so the same contents in a different file are a different
+        // entry.
+        path.hash(&mut hasher);
+        contents.hash(&mut hasher);
+
+        self.directory
+            .join(format!("{:016x}.json", hasher.finish()))
+    }
+
+    pub fn load(&self, path: &Utf8Path, contents: &str) -> Option<Vec<(Expression, Span)>> {
+        let entry = fs::read_to_string(self.entry_path(path, contents)).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&entry).ok()?;
+
+        if entry.version == VERSION && entry.path == path.as_str() && entry.contents == contents {
+            Some(entry.ast.into_owned())
+        } else {
+            None
+        }
+    }
+
+    // Only ASTs of files without any errors should be stored, as the errors of a file are reported while parsing it.
+    pub fn store(&self, path: &Utf8Path, contents: &str, ast: &[(Expression, Span)]) {
+        let entry = CacheEntry {
+            version: Cow::from(VERSION),
+            path: Cow::from(path.as_str()),
+            contents: Cow::from(contents),
+            ast: Cow::from(ast),
+        };
+
+        if let Ok(entry) = serde_json::to_string(&entry) {
+            let _ = fs::create_dir_all(&self.directory)
+                .and_then(|_| fs::write(self.entry_path(path, contents), entry));
+        }
+    }
+}
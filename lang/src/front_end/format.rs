@@ -0,0 +1,377 @@
+use crate::core::ast::{is_closure_name, Expression, Type, TypeHint};
+use crate::core::span::Span;
+use crate::core::Name;
+use crate::front_end::lex::keyword;
+
+const INDENT: &str = "    ";
+
+fn format_type(data_type: &Type) -> String {
+    match data_type {
+        Type::Integer => String::from("Int"),
+        Type::RangedInteger { min, max } => format!("Int<{}, {}>", min, max),
+        Type::SizedInteger(width) => width.to_string(),
+        Type::Boolean => String::from("Bool"),
+        Type::String => String::from("Str"),
+        Type::Structure(id) => id.to_string(),
+        Type::Reference(data_type) => format!("&{}", format_type(data_type)),
+        Type::Optional(data_type) => format!("{}?", format_type(data_type)),
+    }
+}
+
+// Names that are keywords could only have been written as raw identifiers.
+fn format_name(name: Name) -> String {
+    if keyword(&name).is_some() {
+        format!("r#{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn format_binding(binding: &TypeHint<(Name, Span)>) -> String {
+    match &binding.type_hint {
+        Some((data_type, _)) => format!(
+            "{}: {}",
+            format_name(binding.value.0),
+            format_type(data_type)
+        ),
+        None => format_name(binding.value.0),
+    }
+}
+
+// These don't need a `;` after them when they are inside of a block.
+fn is_block_like(expression: &Expression) -> bool {
+    if let Expression::Function { name, .. } = expression {
+        return !is_closure_name(&name.value.0);
+    }
+
+    matches!(
+        expression,
+        Expression::Conditional { .. }
+            | Expression::Match { .. }
+            | Expression::Loop { .. }
+            | Expression::Structure { .. }
+            | Expression::Enumeration { .. }
+            | Expression::Instance { .. }
+            | Expression::Block { .. }
+    )
+}
+
+// The AST doesn't store comments, so they are recovered from the text in between two expressions.
+fn comments(gap: &str) -> Vec<&str> {
+    let mut comments = Vec::new();
+    let mut rest = gap;
+
+    while let Some(start) = rest.find("//").into_iter().chain(rest.find("/*")).min() {
+        rest = &rest[start..];
+
+        let end = if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else {
+            rest.find("*/").map_or(rest.len(), |end| end + 2)
+        };
+
+        comments.push(rest[..end].trim_end());
+        rest = &rest[end..];
+    }
+
+    comments
+}
+
+// A blank line in between two expressions is kept, but any more than one are collapsed.
+fn has_blank_line(gap: &str) -> bool {
+    gap.split('\n')
+        .skip(1)
+        .collect::<Vec<_>>()
+        .split_last()
+        .is_some_and(|(_, lines)| lines.iter().any(|line| line.trim().is_empty()))
+}
+
+struct Formatter<'a> {
+    text: &'a str,
+    output: String,
+    indent: usize,
+}
+
+impl<'a> Formatter<'a> {
+    fn new_line(&mut self) {
+        self.output.push('\n');
+        self.output.push_str(&INDENT.repeat(self.indent));
+    }
+
+    fn verbatim(&mut self, span: &Span) {
+        self.output.push_str(&self.text[span.range.clone()]);
+    }
+
+    // Comments are only kept in between the expressions of a sequence. Any other text of an expression, which isn't
+    // in one of the parts it's formatted from, is rewritten, so the expression is kept as written if it has comments.
+    fn has_stray_comments(&self, span: &Span, parts: &[&Span]) -> bool {
+        let mut parts = parts.to_vec();
+        parts.sort_by_key(|part| part.range.start);
+
+        let mut last = span.range.start;
+
+        for part in parts {
+            if !comments(&self.text[last..part.range.start]).is_empty() {
+                return true;
+            }
+
+            last = part.range.end;
+        }
+
+        !comments(&self.text[last..span.range.end]).is_empty()
+    }
+
+    // Any comments in between two expressions are kept, each on it's own line. A gap is `first` when nothing was
+    // formatted before it, and `last` when nothing will be formatted after it.
+    fn gap(&mut self, start: usize, end: usize, first: bool, last: bool) {
+        let gap = &self.text[start..end];
+        let comments = comments(gap);
+
+        if !(first || last && comments.is_empty()) && has_blank_line(gap) {
+            self.output.push('\n');
+        }
+
+        for comment in comments {
+            if !(first && self.output.is_empty()) {
+                self.new_line();
+            }
+
+            self.output.push_str(comment);
+        }
+    }
+
+    // Formats a sequence of expressions, each on it's own line. `start` and `end` bound the text the sequence was
+    // parsed from, so that comments before the first and after the last expressions are kept.
+    fn sequence<'b>(
+        &mut self,
+        expressions: impl Iterator<Item = (&'b (Expression, Span), bool)>,
+        start: usize,
+        end: usize,
+    ) {
+        let mut last = start;
+
+        for (index, ((expression, span), terminate)) in expressions.enumerate() {
+            self.gap(last, span.range.start, index == 0, false);
+
+            if !self.output.is_empty() {
+                self.new_line();
+            }
+
+            self.expression(expression, span);
+
+            if terminate && !is_block_like(expression) {
+                self.output.push(';');
+            }
+
+            last = span.range.end;
+        }
+
+        self.gap(last, end, last == start, true);
+    }
+
+    fn block(&mut self, expression: &Expression, span: &Span) {
+        match expression {
+            Expression::Block { expressions, tail } => {
+                if expressions.is_empty()
+                    && tail.is_none()
+                    && comments(&self.text[span.range.clone()]).is_empty()
+                {
+                    return self.output.push_str("{}");
+                }
+
+                self.output.push('{');
+                self.indent += 1;
+
+                self.sequence(
+                    expressions
+                        .iter()
+                        .map(|expression| (expression, true))
+                        .chain(tail.as_deref().map(|expression| (expression, false))),
+                    span.range.start + 1,
+                    span.range.end - 1,
+                );
+
+                self.indent -= 1;
+                self.new_line();
+                self.output.push('}');
+            }
+            _ => self.verbatim(span),
+        }
+    }
+
+    // Only functions, blocks, conditionals, loops and declarations are laid out by the formatter, anything else is kept
+    // as it was written. Parenthesized expressions are kept as well, since their spans include the parentheses.
+    fn expression(&mut self, expression: &Expression, span: &Span) {
+        if self.text[span.range.clone()].starts_with('(') {
+            return self.verbatim(span);
+        }
+
+        match expression {
+            Expression::Function { body, .. } if self.has_stray_comments(span, &[&body.1]) => {
+                self.verbatim(span)
+            }
+            Expression::Conditional { branches, tail }
+                if self.has_stray_comments(
+                    span,
+                    &branches
+                        .iter()
+                        .flat_map(|(condition, body)| [&condition.1, &body.1])
+                        .chain(tail.as_deref().map(|tail| &tail.1))
+                        .collect::<Vec<_>>(),
+                ) =>
+            {
+                self.verbatim(span)
+            }
+            Expression::Loop { body, .. } if self.has_stray_comments(span, &[&body.1]) => {
+                self.verbatim(span)
+            }
+            Expression::Declaration { value, .. } if self.has_stray_comments(span, &[&value.1]) => {
+                self.verbatim(span)
+            }
+            Expression::Function {
+                name,
+                parameters,
+                body,
+            } => {
+                let parameters = parameters
+                    .iter()
+                    .map(format_binding)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                if is_closure_name(&name.value.0) {
+                    self.output.push_str(&format!("|{}|", parameters));
+                } else {
+                    self.output.push_str(&format!(
+                        "func {}({})",
+                        format_name(name.value.0),
+                        parameters
+                    ));
+                }
+
+                if let Some((return_type, _)) = &name.type_hint {
+                    self.output
+                        .push_str(&format!(" -> {}", format_type(return_type)));
+                }
+
+                self.output.push(' ');
+                self.block(&body.0, &body.1);
+            }
+            Expression::Block { .. } => self.block(expression, span),
+            Expression::Conditional { branches, tail } => {
+                for (index, ((condition, condition_span), (body, body_span))) in
+                    branches.iter().enumerate()
+                {
+                    self.output
+                        .push_str(if index == 0 { "if " } else { " else if " });
+                    self.expression(condition, condition_span);
+                    self.output.push(' ');
+                    self.block(body, body_span);
+                }
+
+                if let Some(tail) = tail {
+                    self.output.push_str(" else ");
+                    self.block(&tail.0, &tail.1);
+                }
+            }
+            Expression::Loop { label, body } => {
+                if let Some((label, _)) = label {
+                    self.output.push_str(&format!("'{}: ", label));
+                }
+
+                self.output.push_str("loop ");
+                self.block(&body.0, &body.1);
+            }
+            Expression::Declaration {
+                name,
+                value,
+                mutable,
+            } => {
+                self.output.push_str(&format!(
+                    "var {}{} = ",
+                    if *mutable { "mut " } else { "" },
+                    format_binding(name)
+                ));
+                self.expression(&value.0, &value.1);
+            }
+            _ => self.verbatim(span),
+        }
+    }
+}
+
+// Formats a whole file into it's canonical layout. The text must be the same one the program was parsed from, and the
+// program must not contain any errors.
+pub fn format(text: &str, program: &[(Expression, Span)]) -> String {
+    let mut formatter = Formatter {
+        text,
+        output: String::new(),
+        indent: 0,
+    };
+
+    formatter.sequence(
+        program.iter().map(|expression| (expression, false)),
+        0,
+        text.len(),
+    );
+
+    // An empty file is kept empty, instead of becoming a single blank line.
+    if !formatter.output.is_empty() {
+        formatter.output.push('\n');
+    }
+
+    formatter.output
+}
+
+#[cfg(test)]
+mod tests {
+    use camino::Utf8PathBuf;
+    use internment::Intern;
+
+    use super::format;
+    use crate::core::{error::Errors, span::SourceMap};
+    use crate::front_end::generate_ast;
+
+    // Formats a file on it's own, which has to parse without any errors.
+    fn format_text(text: &str) -> String {
+        let mut errors = Errors {
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            sources: SourceMap::new(),
+        };
+
+        let program = generate_ast(
+            Intern::new(Utf8PathBuf::from("main.bell")),
+            text,
+            &mut errors,
+        );
+
+        assert!(errors.is_empty(), "failed to parse:\n{}", text);
+
+        format(text, &program)
+    }
+
+    #[test]
+    fn formatting_is_idempotent() {
+        let text = "// The entry point.
+func main()  ->  Int {
+    var x: Int = 1;   var mut y = ({ x });
+
+
+    if (x == 1) { y = 2; } else if x > 1 { y = 3 }
+    'outer:  loop { break 'outer 1; }
+    func /* kept */ helper(a: Int) {a}
+    var /* kept */ z = 4;
+    /* trailing */
+    y
+}
+";
+        let once = format_text(text);
+        let twice = format_text(&once);
+
+        assert_eq!(once, twice);
+        assert!(once.contains("if (x == 1)"));
+        assert!(once.contains("({ x })"));
+        assert!(once.contains("/* kept */ helper"));
+        assert!(once.contains("/* kept */ z"));
+    }
+}
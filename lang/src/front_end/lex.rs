@@ -6,37 +6,100 @@ use chumsky::{Parser, Stream};
 
 use internment::Intern;
 
-use crate::core::error::{Element, Error, Errors, ParseError, Pattern};
-use crate::core::span::Span;
+use crate::core::error::{Error, Errors, ParseError};
+use crate::core::span::{Span, SpanOrigin};
 use crate::core::token::{MetaToken, Token};
 
+// Identifiers with these names are lexed as the returned tokens, unless they are written as raw identifiers.
+pub fn keyword(identifier: &str) -> Option<Token> {
+    Some(match identifier {
+        "var" => Token::Variable,
+        "mut" => Token::Mutable,
+        "loop" => Token::Loop,
+        "break" => Token::Break,
+        "continue" => Token::Continue,
+        "return" => Token::Return,
+        "func" => Token::Function,
+        "struct" => Token::Structure,
+        "enum" => Token::Enumeration,
+        "match" => Token::Match,
+        "if" => Token::If,
+        "else" => Token::Else,
+        "use" => Token::Use,
+        "as" => Token::As,
+        "null" => Token::Null,
+        "_" => Token::Underscore,
+        "true" => Token::Boolean(true),
+        "false" => Token::Boolean(false),
+        _ => return None,
+    })
+}
+
 pub fn lexer() -> impl Parser<char, Vec<(MetaToken, Span)>, Error = ParseError> {
     recursive(|meta_tokens| {
         let identifier = text::ident::<char, _>()
-            .map(|identifier| match identifier.as_str() {
-                "var" => Token::Variable,
-                "loop" => Token::Loop,
-                "break" => Token::Break,
-                "continue" => Token::Continue,
-                "return" => Token::Return,
-                "func" => Token::Function,
-                "struct" => Token::Structure,
-                "if" => Token::If,
-                "else" => Token::Else,
-                "use" => Token::Use,
-                "true" => Token::Boolean(true),
-                "false" => Token::Boolean(false),
-                _ => Token::Name(Intern::new(identifier)),
+            .map(|identifier| {
+                keyword(&identifier).unwrap_or_else(|| Token::Name(Intern::new(identifier)))
             })
             .labelled("identifier");
 
-        let integer = text::int(10)
-            // The input must be a valid number due to how it's defined.
-            .map(|characters: String| Token::Int(characters.parse::<i32>().unwrap()))
+        // Raw identifiers let keywords be used as names, such as `r#loop`. Their span only covers the name itself, so
+        // that labels pointing at them don't include the prefix.
+        let raw_identifier = just("r#")
+            .ignore_then(
+                text::ident().map_with_span(|identifier: String, span: Span| {
+                    (MetaToken::Token(Token::Name(Intern::new(identifier))), span)
+                }),
+            )
+            .labelled("raw identifier");
+
+        // A `-` directly followed by digits is lexed as part of the literal, see `split_negative_literals`.
+        let integer = just('-')
+            .or_not()
+            .then(text::int(10))
+            // The input is always made of digits due to how it's defined, but it may still be out of range.
+            .validate(|(sign, characters): (Option<char>, String), span, emit| {
+                let literal = format!("{}{}", if sign.is_some() { "-" } else { "" }, characters);
+
+                Token::Int(literal.parse::<i32>().unwrap_or_else(|_| {
+                    emit(ParseError::custom(
+                        span,
+                        format!(
+                            "the integer {} is out of range, integers must be between {} and {}",
+                            literal,
+                            i32::MIN,
+                            i32::MAX
+                        ),
+                    ));
+
+                    0
+                }))
+            })
             .labelled("integer");
 
-        let symbol = choice((
+        // These are split from the other symbols since `choice` only supports a limited amount of
+        // alternatives. They must come first so that `+=` isn't lexed as `+` and then `=`.
+        let compound_assign = choice((
+            just("+=").to(Token::AddAssign),
+            just("-=").to(Token::SubtractAssign),
+            just("*=").to(Token::MultiplyAssign),
+            just("/=").to(Token::DivideAssign),
+            just("%=").to(Token::ModuloAssign),
+        ));
+
+        // Labels name loops, such as `'outer: loop { ... }`.
+        let label = just('\'')
+            .ignore_then(text::ident())
+            .map(|label: String| Token::Label(Intern::new(label)))
+            .labelled("label");
+
+        // Intrinsics are called with a leading `@`, such as `@typeof(value)`.
+        let at = just("@").to(Token::At);
+
+        let symbol = compound_assign.or(at).or(choice((
             just("+").to(Token::Add),
+            // This must come before `-`, which would otherwise split the arrow in two.
+            just("->").to(Token::Arrow),
             just("-").to(Token::Minus),
             just("*").to(Token::Multiply),
             just("/").to(Token::Divide),
@@ -45,23 +108,30 @@ pub fn lexer() -> impl Parser<char, Vec<(MetaToken, Span)>, Error = ParseError>
             just(">=").to(Token::GreaterOrEqual),
             just("==").to(Token::Equal),
             just("!=").to(Token::NotEqual),
+            just("=>").to(Token::FatArrow),
             just("&").to(Token::Reference),
-            just("||").to(Token::Or),
+            // `choice` can't take any more alternatives, so `|` shares one with `||`, which must be tried first.
+            just("||").to(Token::Or).or(just("|").to(Token::Pipe)),
             just("&&").to(Token::And),
             just("=").to(Token::Assign),
             just("::").to(Token::ModuleAcess),
             just(":").to(Token::Specify),
-            just(".").to(Token::Of),
-            just("->").to(Token::Arrow),
+            // Like `|`, `..` shares an alternative with the token it starts with.
+            just("..").to(Token::Spread).or(just(".").to(Token::Of)),
             just(";").to(Token::Terminate),
             just("<").to(Token::Lesser),
             just(">").to(Token::Greater),
             just("(").to(Token::Left),
             just(")").to(Token::Right),
             just(",").to(Token::Separate),
-        ));
+            just("?").to(Token::Question),
+        )));
 
-        let token = identifier.or(integer).or(symbol).map(MetaToken::Token);
+        let token = identifier
+            .or(integer)
+            .or(label)
+            .or(symbol)
+            .map(MetaToken::Token);
 
         let block = meta_tokens
             .clone()
@@ -93,21 +163,29 @@ pub fn lexer() -> impl Parser<char, Vec<(MetaToken, Span)>, Error = ParseError>
             .boxed()
             .labelled("string");
 
-        let meta_token = token
-            .or(block)
-            .or(string)
-            .map_with_span(|token, span: Span| (token, span))
+        // Raw identifiers must come first, since `r` would otherwise be lexed as an identifier of it's own.
+        let meta_token = raw_identifier
+            .or(token
+                .or(block)
+                .or(string)
+                .map_with_span(|token, span: Span| (token, span)))
             .padded();
 
-        let block_comment = just("/*").then(any().repeated()).then(just("*/")).ignored();
+        let block_comment = just("/*").then(take_until(just("*/"))).ignored();
 
         let comment = just("//")
-            .then(any().repeated().then(text::newline().or(end())))
+            .then(take_until(text::newline().or(end())))
             .ignored();
 
         let comments = block_comment.or(comment).padded().repeated();
 
-        meta_token.repeated().padded_by(comments)
+        // Comments may be between any two tokens, as well as in a file without any. Without a token or comment to pad,
+        // whitespace on it's own would be left over.
+        meta_token
+            .padded_by(comments.clone())
+            .repeated()
+            .padded_by(comments)
+            .padded()
     })
     .then(Parser::<char, _>::map_with_span(end(), |_, span: Span| {
         (MetaToken::Token(Token::EndOfFile), span)
@@ -118,38 +196,182 @@ pub fn lexer() -> impl Parser<char, Vec<(MetaToken, Span)>, Error = ParseError>
     })
 }
 
+fn ends_operand(token: Option<&(MetaToken, Span)>) -> bool {
+    matches!(
+        token,
+        Some((
+            MetaToken::Token(Token::Int(_) | Token::Boolean(_) | Token::Name(_) | Token::Right)
+                | MetaToken::FormatString(_)
+                | MetaToken::Block(_),
+            _
+        ))
+    )
+}
+
+// Negative literals are needed so that values like `-2147483648` can be written at all, since their magnitude doesn't fit
+// in an `Int`. The disambiguation rule is as follows: a `-` that is directly followed by digits is part of the literal,
+// unless the token before it ends an operand (an integer, boolean, string, identifier, `)` or block), in which case it's a
+// binary subtraction. So `a -1`, `a-1` and `{ a }-1` are subtractions, while `(-1)`, `f(-1)` and `a - -1` contain a
+// negative literal.
+fn split_negative_literals(text: &str, tokens: Vec<(MetaToken, Span)>) -> Vec<(MetaToken, Span)> {
+    let mut result: Vec<(MetaToken, Span)> = Vec::with_capacity(tokens.len());
+
+    for (token, span) in tokens {
+        match token {
+            MetaToken::Token(Token::Int(value))
+                if text
+                    .get(span.range.clone())
+                    .is_some_and(|literal| literal.starts_with('-'))
+                    && ends_operand(result.last()) =>
+            {
+                // The magnitude of the smallest integer can't be represented, so it is kept as a literal, and the parser
+                // will report it as an unexpected integer.
+                if let Some(magnitude) = value.checked_neg() {
+                    result.push((
+                        MetaToken::Token(Token::Minus),
+                        Span {
+                            path: span.path,
+                            range: span.range.start..span.range.start + 1,
+                            origin: SpanOrigin::Written,
+                        },
+                    ));
+                    result.push((
+                        MetaToken::Token(Token::Int(magnitude)),
+                        Span {
+                            path: span.path,
+                            range: span.range.start + 1..span.range.end,
+                            origin: SpanOrigin::Written,
+                        },
+                    ));
+                } else {
+                    result.push((MetaToken::Token(Token::Int(value)), span));
+                }
+            }
+            MetaToken::Block(tokens) => {
+                result.push((MetaToken::Block(split_negative_literals(text, tokens)), span))
+            }
+            MetaToken::FormatString(elements) => result.push((
+                MetaToken::FormatString(
+                    elements
+                        .into_iter()
+                        .map(|(tokens, span)| (split_negative_literals(text, tokens), span))
+                        .collect(),
+                ),
+                span,
+            )),
+            token => result.push((token, span)),
+        }
+    }
+
+    result
+}
+
+// The lexer and the parser both recurse into blocks and parentheses, so anything nested deeper than this is rejected before
+// it's lexed, instead of overflowing the stack.
+const MAX_NESTING: usize = 128;
+
+// Braces and parentheses are counted wherever they are, including in strings and comments. This can only reject
+// something that is already nested very deeply.
+fn find_excessive_nesting(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+
+    for (index, character) in text.char_indices() {
+        match character {
+            '{' | '(' => {
+                depth += 1;
+
+                if depth > MAX_NESTING {
+                    return Some(index);
+                }
+            }
+            '}' | ')' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+    }
+
+    None
+}
+
+// The parser expects every token stream to end with `Token::EndOfFile`, even when nothing else could be lexed.
+fn end_of_file(path: Intern<Utf8PathBuf>, text: &str) -> (MetaToken, Span) {
+    (
+        MetaToken::Token(Token::EndOfFile),
+        Span {
+            path,
+            // Using a saturating subtraction since the file may be empty.
+            range: (text.len().saturating_sub(1))..text.len().max(1),
+            origin: SpanOrigin::Written,
+        },
+    )
+}
+
+// Any input may be lexed, invalid input is reported through `global_errors` rather than causing a panic.
 pub fn lex(
     path: Intern<Utf8PathBuf>,
     text: &str,
     global_errors: &mut Errors,
 ) -> Vec<(MetaToken, Span)> {
+    if let Some(index) = find_excessive_nesting(text) {
+        global_errors.insert_error(Error::BasicAt {
+            message: format!(
+                "this is nested too deeply, at most {} blocks and parentheses may be nested in each other",
+                MAX_NESTING
+            ),
+            span: Span {
+                path,
+                range: index..index + 1,
+                origin: SpanOrigin::Written,
+            },
+        });
+
+        return vec![end_of_file(path, text)];
+    }
+
     let (tokens, errors) = lexer().parse_recovery(Stream::from_iter(
-        Span {
-            path,
-            // Using a saturating subtraction since the file may be empty.
-            range: (text.len().saturating_sub(1))..text.len().max(1),
-        },
+        end_of_file(path, text).1,
         text.char_indices().map(|(index, character)| {
             (
                 character,
+                // Spans are byte ranges, so that they can be used to index the source directly.
                 Span {
                     path,
-                    range: index..index + 1,
+                    range: index..index + character.len_utf8(),
+                    origin: SpanOrigin::Written,
                 },
             )
         }),
     ));
 
-    global_errors.extend(errors.into_iter().map(|error| Error::Unexpected {
-        expected: error.expected,
-        found: Element {
-            // If it's None, it must have encountered the end of file.
-            value: error.found.unwrap_or(Pattern::Construct("end of file")),
-            span: error.span,
-        },
-        reason: error.reason,
-        while_parsing: error.label,
-    }));
+    global_errors.extend(errors.into_iter().map(Error::from));
+
+    split_negative_literals(
+        text,
+        tokens.unwrap_or_else(|| vec![end_of_file(path, text)]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use camino::Utf8PathBuf;
+
+    use crate::core::error::Error;
+    use crate::{check_source, ProjectKind};
+
+    #[test]
+    fn spans_are_byte_offsets_into_crlf_sources() {
+        let src = "func main() {\r\n    // A comment.\r\n    missing\r\n}\r\n";
+        let errors = check_source(Utf8PathBuf::from("main.bell"), src, ProjectKind::Executable)
+            .err()
+            .expect("the file should fail to check");
+
+        let span = errors
+            .iter()
+            .find_map(|error| match error {
+                Error::MissingId { id } => Some(&id.span),
+                _ => None,
+            })
+            .expect("`missing` should be reported");
 
-    tokens.unwrap_or_else(|| Vec::new())
+        assert_eq!(&src[span.range.clone()], "missing");
+    }
 }
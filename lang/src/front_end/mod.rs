@@ -1,18 +1,35 @@
 use crate::core::ast::Expression;
-use crate::core::{error::Errors, span::Span};
+use crate::core::{error::Errors, progress, span::Span};
 use camino::Utf8PathBuf;
 use internment::Intern;
 
 // The default front-end of Bell. One could use structures defined in `ast` to make another front-end.
+#[cfg(feature = "fs")]
+pub mod cache;
+pub mod format;
 pub mod lex;
 pub mod module;
 pub mod parse;
 
+// The prelude is a program whose items are in scope in every module.
+const PRELUDE: &str = include_str!("../prelude.bell");
+
 // Perform the whole frontend on the source.
 pub fn generate_ast(
     path: Intern<Utf8PathBuf>,
     text: &str,
     errors: &mut Errors,
 ) -> Vec<(Expression, Span)> {
-    parse::parse(lex::lex(path, text, errors), errors)
+    progress::enter("parsing", Some(path));
+
+    parse::parse(path, lex::lex(path, text, errors), errors)
+}
+
+pub fn generate_prelude(errors: &mut Errors) -> Vec<(Expression, Span)> {
+    let path = Intern::new(Utf8PathBuf::from("<prelude>"));
+
+    // The prelude isn't a file, so it's source must be inserted for any error in it to be displayed.
+    errors.insert_source(path, PRELUDE.to_string());
+
+    generate_ast(path, PRELUDE, errors)
 }
@@ -1,62 +1,99 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
 
 use crate::core::{
-    ast::{Id, Module},
+    ast::{Expression, Id, Module},
     error::Errors,
+    span::Span,
     Name,
 };
 use crate::core::{error::Error, file::Entry};
 use crate::front_end;
+#[cfg(feature = "fs")]
+use crate::front_end::cache::AstCache;
 
+use camino::Utf8PathBuf;
 use internment::Intern;
 
+// The root module of a project directory.
+pub const ENTRY_POINT: &str = "main.bell";
+
 struct EntryTransformer<'a> {
-    adjacent_names: HashSet<Name>,
-    current_parent_id: Id,
+    // The names of the modules next to the current one, and the paths they came from.
+    adjacent_names: HashMap<Name, Intern<Utf8PathBuf>>,
+    // The path of the directory modules surrounding the current one.
+    current_parent: Vec<Name>,
+    #[cfg(feature = "fs")]
+    cache: Option<&'a AstCache>,
     errors: &'a mut Errors,
 }
 
 // The entry transformers checks naming based on the rule that every module must have a unique name relative to it's adjacent modules
 impl<'a> EntryTransformer<'a> {
+    fn insert_name(&mut self, name: Name, path: Intern<Utf8PathBuf>) {
+        if let Some(&first) = self.adjacent_names.get(&name) {
+            self.errors.insert_error(Error::ConflictingModuleNames {
+                parent: Id::new(self.current_parent.clone()),
+                name,
+                first: (*first).clone(),
+                second: (*path).clone(),
+            })
+        } else {
+            self.adjacent_names.insert(name, path);
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    fn parse(&mut self, path: Intern<Utf8PathBuf>, contents: &str) -> Vec<(Expression, Span)> {
+        match self.cache.and_then(|cache| cache.load(&path, contents)) {
+            Some(ast) => ast,
+            None => {
+                let error_count = self.errors.len();
+                let ast = front_end::generate_ast(path, contents, self.errors);
+
+                if let Some(cache) = self.cache {
+                    if self.errors.len() == error_count {
+                        cache.store(&path, contents, &ast);
+                    }
+                }
+
+                ast
+            }
+        }
+    }
+
+    // The cache lives on the filesystem, so without it every file is parsed.
+    #[cfg(not(feature = "fs"))]
+    fn parse(&mut self, path: Intern<Utf8PathBuf>, contents: &str) -> Vec<(Expression, Span)> {
+        front_end::generate_ast(path, contents, self.errors)
+    }
+
     fn transform(&mut self, entry: Entry) -> Module {
         match entry {
             Entry::File { path, contents } => {
-                let ast = front_end::generate_ast(path, &contents, self.errors);
+                let ast = self.parse(path, &contents);
                 let name = Intern::new(path.file_stem().unwrap().to_string());
 
-                if self.adjacent_names.get(&name).is_some() {
-                    self.errors.insert_error(Error::ConflictingModuleNames {
-                        parent: self.current_parent_id.clone(),
-                        name,
-                    })
-                }
-
+                self.insert_name(name, path);
                 self.errors.insert_source(path, contents);
-                self.adjacent_names.insert(name);
 
                 Module::Program { name, ast }
             }
             Entry::Directory { path, entries } => {
                 let name = Intern::new(path.file_stem().unwrap().to_string());
 
-                if self.adjacent_names.get(&name).is_some() {
-                    self.errors.insert_error(Error::ConflictingModuleNames {
-                        parent: self.current_parent_id.clone(),
-                        name,
-                    })
-                }
-
-                self.current_parent_id.0.push(name);
+                self.insert_name(name, path);
+                self.current_parent.push(name);
 
+                // The modules inside of the directory are only adjacent to each other, so the names next to the directory
+                // itself are put aside until they are done.
+                let adjacent_names = std::mem::take(&mut self.adjacent_names);
                 let modules = entries
                     .into_iter()
                     .map(|entry| self.transform(entry))
                     .collect();
 
-                self.current_parent_id.0.pop();
-
-                // Once we finish using this hash set to make sure every adjacent name is unique, we need throw them out to avoid erroneous errors.
-                self.adjacent_names.clear();
+                self.adjacent_names = adjacent_names;
+                self.current_parent.pop();
 
                 Module::Submodule { name, modules }
             }
@@ -64,12 +101,46 @@ impl<'a> EntryTransformer<'a> {
     }
 }
 
-pub fn from(entry: Entry, errors: &mut Errors) -> Module {
-    let mut transformer = EntryTransformer {
-        adjacent_names: HashSet::new(),
-        current_parent_id: Id::new(Vec::new()),
-        errors,
-    };
+fn transform_root(mut transformer: EntryTransformer, entry: Entry) -> Module {
+    if let Entry::Directory { path, entries } = &entry {
+        let has_entry_point = entries.iter().any(|entry| {
+            matches!(entry, Entry::File { path, .. } if path.file_name() == Some(ENTRY_POINT))
+        });
+
+        if !has_entry_point {
+            transformer.errors.insert_error(Error::MissingEntryPoint {
+                expected: path.join(ENTRY_POINT),
+            });
+        }
+    }
 
     transformer.transform(entry)
 }
+
+pub fn from(entry: Entry, errors: &mut Errors) -> Module {
+    transform_root(
+        EntryTransformer {
+            adjacent_names: HashMap::new(),
+            current_parent: Vec::new(),
+            #[cfg(feature = "fs")]
+            cache: None,
+            errors,
+        },
+        entry,
+    )
+}
+
+// Like `from`, but files whose ASTs are in the cache aren't parsed again, and the ASTs of those that are parsed are stored
+// in it.
+#[cfg(feature = "fs")]
+pub fn from_cached(entry: Entry, cache: Option<&AstCache>, errors: &mut Errors) -> Module {
+    transform_root(
+        EntryTransformer {
+            adjacent_names: HashMap::new(),
+            current_parent: Vec::new(),
+            cache,
+            errors,
+        },
+        entry,
+    )
+}
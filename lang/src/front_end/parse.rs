@@ -12,10 +12,11 @@ use chumsky::Span as SpanTrait;
 use crate::{ast::TypeHint, core::error::Errors};
 use internment::Intern;
 
-use crate::core::ast::{Expression, Id, Type};
-use crate::core::error::{Element, Error, ParseError, Pattern};
-use crate::core::span::Span;
+use crate::core::ast::{closure_name, Expression, Id, IntegerWidth, Type};
+use crate::core::error::{Error, ParseError};
+use crate::core::span::{Span, SpanOrigin};
 use crate::core::token::{MetaToken, Token};
+use crate::core::Name;
 
 impl Display for Span {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
@@ -31,6 +32,7 @@ impl SpanTrait for Span {
         Span {
             path: context,
             range,
+            origin: SpanOrigin::Written,
         }
     }
 
@@ -50,7 +52,20 @@ impl SpanTrait for Span {
 struct TokenIterator(Vec<(MetaToken, Span)>);
 
 impl TokenIterator {
-    fn new(mut tokens: Vec<(MetaToken, Span)>) -> Self {
+    fn new(path: Intern<Utf8PathBuf>, mut tokens: Vec<(MetaToken, Span)>) -> Self {
+        // The lexer always ends it's output with an EOF token, but if lexing failed completely there may be no tokens at all.
+        // The rest of the parser relies on the EOF token, so in that case it's placed at the start of the file.
+        if tokens.is_empty() {
+            tokens.push((
+                MetaToken::Token(Token::EndOfFile),
+                Span {
+                    path,
+                    range: 0..1,
+                    origin: SpanOrigin::Written,
+                },
+            ));
+        }
+
         tokens.reverse();
 
         TokenIterator(tokens)
@@ -65,6 +80,16 @@ impl Iterator for TokenIterator {
     }
 }
 
+enum InstanceElement {
+    Field((Name, Span), (Expression, Span)),
+    Base((Expression, Span)),
+}
+
+enum Postfix {
+    Access((Name, Span)),
+    Call((Vec<(Expression, Span)>, Span)),
+}
+
 impl TokenIterator {
     fn get_end_span(&self) -> Span {
         self.0.first().unwrap().1.clone()
@@ -81,6 +106,23 @@ fn delimit_tokens(
     Box::new(iter::once(start).chain(tokens).chain(iter::once(end)))
 }
 
+// The first and last characters of a meta token are it's delimiters. A meta token produced while recovering from an error
+// may have an empty span, in which case both delimiters are given the empty span rather than one outside of it.
+fn delimiter_spans(span: &Span) -> (Span, Span) {
+    let Range { start, end } = span.range.clone();
+
+    (
+        Span {
+            range: start..(start + 1).min(end),
+            ..span.clone()
+        },
+        Span {
+            range: end.saturating_sub(1).max(start)..end,
+            ..span.clone()
+        },
+    )
+}
+
 impl<'a> From<TokenIterator> for Stream<'a, Token, Span, Box<dyn Iterator<Item = (Token, Span)>>> {
     fn from(
         mut tokens: TokenIterator,
@@ -95,66 +137,33 @@ impl<'a> From<TokenIterator> for Stream<'a, Token, Span, Box<dyn Iterator<Item =
             // I believe it's possible the characters we use for this kind of stuff: quotes and curly brackets may be changed one
             // day to have a different length. In that case, I might have to rethink how this part should be done.
             // TODO: Do this in a cleaner, more extensible way.
-            MetaToken::FormatString(elements) => Flat::Many(delimit_tokens(
-                Box::new(
-                    elements
-                        .into_iter()
-                        .map(|meta_tokens| {
-                            delimit_tokens(
-                                Box::new(meta_tokens.0.into_iter()),
-                                (
-                                    MetaToken::Token(Token::CurlyLeft),
-                                    Span {
-                                        path: meta_tokens.1.path,
-                                        range: meta_tokens.1.range.start
-                                            ..meta_tokens.1.range.start + 1,
-                                    },
-                                ),
-                                (
-                                    MetaToken::Token(Token::CurlyRight),
-                                    Span {
-                                        path: meta_tokens.1.path,
-                                        range: (meta_tokens.1.range.end - 1)
-                                            ..meta_tokens.1.range.end,
-                                    },
-                                ),
-                            )
-                        })
-                        .flatten(),
-                ),
-                (
-                    MetaToken::Token(Token::Quote),
-                    Span {
-                        path: span.path,
-                        range: span.range.start..span.range.start + 1,
-                    },
-                ),
-                (
-                    MetaToken::Token(Token::Quote),
-                    Span {
-                        path: span.path,
-                        range: (span.range.end - 1)..span.range.end,
-                    },
-                ),
-            )),
+            MetaToken::FormatString(elements) => {
+                let (start, end) = delimiter_spans(&span);
+
+                Flat::Many(delimit_tokens(
+                    Box::new(elements.into_iter().flat_map(|meta_tokens| {
+                        let (start, end) = delimiter_spans(&meta_tokens.1);
+
+                        delimit_tokens(
+                            Box::new(meta_tokens.0.into_iter()),
+                            (MetaToken::Token(Token::CurlyLeft), start),
+                            (MetaToken::Token(Token::CurlyRight), end),
+                        )
+                    })),
+                    (MetaToken::Token(Token::Quote), start),
+                    (MetaToken::Token(Token::Quote), end),
+                ))
+            }
             MetaToken::Token(token) => Flat::Single((token, span)),
-            MetaToken::Block(tokens) => Flat::Many(delimit_tokens(
-                Box::new(tokens.into_iter()),
-                (
-                    MetaToken::Token(Token::CurlyLeft),
-                    Span {
-                        path: span.path,
-                        range: span.range.start..span.range.start + 1,
-                    },
-                ),
-                (
-                    MetaToken::Token(Token::CurlyRight),
-                    Span {
-                        path: span.path,
-                        range: (span.range.end - 1)..span.range.end,
-                    },
-                ),
-            )),
+            MetaToken::Block(tokens) => {
+                let (start, end) = delimiter_spans(&span);
+
+                Flat::Many(delimit_tokens(
+                    Box::new(tokens.into_iter()),
+                    (MetaToken::Token(Token::CurlyLeft), start),
+                    (MetaToken::Token(Token::CurlyRight), end),
+                ))
+            }
         })
     }
 }
@@ -179,6 +188,7 @@ where
             let span = Span {
                 range: left.1.range.start..right.1.range.end,
                 path: left.1.path,
+                origin: SpanOrigin::Written,
             };
 
             (
@@ -196,6 +206,7 @@ where
 
 fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseError> {
     recursive(|expression| {
+        // Parsers of a single token aren't labelled, since the expected token already says what they are.
         let integer = filter_map(|span, token| match token {
             Token::Int(value) => Ok((Expression::Int(value), span)),
             _ => Err(ParseError::expected_input_found(
@@ -204,8 +215,7 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
                 [Some(Token::Int(Default::default()))],
                 Some(token),
             )),
-        })
-        .labelled("integer");
+        });
 
         let boolean = filter_map(|span, token| match token {
             Token::Boolean(value) => Ok((Expression::Boolean(value), span)),
@@ -215,8 +225,7 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
                 [Some(Token::Boolean(Default::default()))],
                 Some(token),
             )),
-        })
-        .labelled("boolean");
+        });
 
         let pure_string = filter_map(|span: Span, token| match token {
             Token::String(value) => Ok((Expression::String(value), span)),
@@ -231,7 +240,7 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
         let string = pure_string
             .or(expression
                 .clone()
-                .delimited_by(Token::CurlyLeft, Token::CurlyRight)
+                .delimited_by(just(Token::CurlyLeft), just(Token::CurlyRight))
                 .map_with_span(|expression: (Expression, Span), span| (expression.0, span))
                 .recover_with(recovery::nested_delimiters(
                     Token::CurlyLeft,
@@ -240,57 +249,45 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
                     |span| (Expression::Error, span),
                 )))
             .repeated()
-            .delimited_by(Token::Quote, Token::Quote)
-            .map_with_span(|string, span: Span| {
+            .delimited_by(just(Token::Quote), just(Token::Quote))
+            .map_with_span(|string: Vec<(Expression, Span)>, string_span: Span| {
+                let call = |name: &str, parameters, span: Span| Expression::Call {
+                    function: Box::new((
+                        Expression::Identifier(Id::new(vec![Intern::new(name.to_string())])),
+                        span.clone(),
+                    )),
+                    parameters: (parameters, span),
+                };
+
                 (
                     string
                         .into_iter()
+                        // The interpolated values are converted into strings, so that the parts are all concatenated
+                        // as strings.
+                        .map(|(part, span)| match part {
+                            Expression::String(_) => (part, span),
+                            _ => {
+                                let generated = span.clone().generated_from(&string_span);
+
+                                (call("to_string", vec![(part, span)], generated.clone()), generated)
+                            }
+                        })
                         .reduce(|accumulator, (next, next_span)| {
                             let span = Span {
                                 range: accumulator.1.range.start..next_span.range.end,
                                 path: accumulator.1.path,
-                            };
+                                origin: SpanOrigin::Written,
+                            }
+                            .generated_from(&string_span);
 
                             (
-                                Expression::Call {
-                                    function: Box::new((
-                                        Expression::Identifier(Id::new(vec![Intern::new(
-                                            "add".to_string(),
-                                        )])),
-                                        Span {
-                                            path: span.path,
-                                            range: accumulator.1.range.start..next_span.range.end,
-                                        },
-                                    )),
-                                    parameters: (
-                                        vec![
-                                            accumulator,
-                                            (
-                                                Expression::Call {
-                                                    function: Box::new((
-                                                        Expression::Identifier(Id::new(vec![
-                                                            Intern::new("to_string".to_string()),
-                                                        ])),
-                                                        // This span shouldn't be used, ever.
-                                                        next_span.clone(),
-                                                    )),
-                                                    parameters: (
-                                                        vec![(next, next_span.clone())],
-                                                        next_span.clone(),
-                                                    ),
-                                                },
-                                                next_span,
-                                            ),
-                                        ],
-                                        span.clone(),
-                                    ),
-                                },
+                                call("concat", vec![accumulator, (next, next_span)], span.clone()),
                                 span,
                             )
                         })
                         .map(|spanned_expression| spanned_expression.0)
                         .unwrap_or(Expression::String(Intern::new(String::new()))),
-                    span,
+                    string_span,
                 )
             })
             .boxed()
@@ -304,8 +301,22 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
                 [Some(Token::Name(Default::default()))],
                 Some(token),
             )),
-        })
-        .labelled("identifier");
+        });
+
+        // A binding may be `_`, in which case the value is discarded instead of being bound.
+        let binding = name
+            .or(just(Token::Underscore)
+                .map_with_span(|_, span| (Intern::new(String::from("_")), span)))
+            .labelled("binding");
+
+        let label = filter_map(|span: Span, token: Token| match token {
+            Token::Label(label) => Ok((label, span)),
+            _ => Err(ParseError::expected_input_found(
+                span,
+                [Some(Token::Label(Default::default()))],
+                Some(token),
+            )),
+        });
 
         let id = name
             .map(|(id, _)| id)
@@ -314,18 +325,71 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
             .map_with_span(|id, span: Span| (Id::new(id), span))
             .labelled("variable");
 
+        let bound = filter_map(|span: Span, token: Token| match token {
+            Token::Int(value) => Ok(value),
+            _ => Err(ParseError::expected_input_found(
+                span,
+                [Some(Token::Int(Default::default()))],
+                Some(token),
+            )),
+        });
+
+        // Both bounds of a ranged integer, such as `Int<0, 100>`, are inclusive.
+        let bounds = bound
+            .then_ignore(just(Token::Separate))
+            .then(bound)
+            .delimited_by(just(Token::Lesser), just(Token::Greater))
+            .labelled("integer range");
+
         let data_type = just(Token::Reference)
             .or_not()
             .map(|token| token.is_some())
-            .then(id.map(|(structure, _)| match structure.0[0].as_str() {
-                "Int" => Type::Integer,
-                "Bool" => Type::Boolean,
-                "Str" => Type::String,
-                _ => Type::Structure(structure),
-            }))
-            .map_with_span(|(is_reference, kind), span: Span| {
-                if is_reference {
-                    (Type::Reference(Box::new(kind)), span)
+            .then(id.then(bounds.or_not()).validate(
+                |((structure, _), bounds), span: Span, emit| {
+                    match (structure.0[0].as_str(), bounds) {
+                        ("Int", Some((min, max))) => {
+                            if min > max {
+                                emit(ParseError::custom(
+                                    span,
+                                    format!(
+                                        "the range {} to {} is empty, the lower bound must not be greater than \
+                                         the upper bound",
+                                        min, max
+                                    ),
+                                ));
+                            }
+
+                            Type::RangedInteger { min, max }
+                        }
+                        (_, Some(_)) => {
+                            emit(ParseError::custom(
+                                span.clone(),
+                                String::from("only `Int` can have bounds"),
+                            ));
+
+                            Type::Structure(structure)
+                        }
+                        ("Int", None) => Type::Integer,
+                        ("I8", None) => Type::SizedInteger(IntegerWidth::I8),
+                        ("I16", None) => Type::SizedInteger(IntegerWidth::I16),
+                        ("I64", None) => Type::SizedInteger(IntegerWidth::I64),
+                        ("Bool", None) => Type::Boolean,
+                        ("Str", None) => Type::String,
+                        (_, None) => Type::Structure(structure),
+                    }
+                },
+            ))
+            .then(just(Token::Question).or_not())
+            .map_with_span(|((is_reference, kind), optional), span: Span| {
+                // The optional applies to the reference, so `&Int?` is a nullable reference.
+                let kind = if is_reference {
+                    Type::Reference(Box::new(kind))
+                } else {
+                    kind
+                };
+
+                if optional.is_some() {
+                    (Type::Optional(Box::new(kind)), span)
                 } else {
                     (kind, span)
                 }
@@ -335,7 +399,7 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
 
         let type_hint = just(Token::Specify)
             .ignore_then(data_type.clone())
-            .labelled("type_hint");
+            .labelled("type hint");
 
         let field = name
             .then(type_hint.clone().or_not())
@@ -346,10 +410,31 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
 
         let block_expression = recursive(|block_expression| {
             let block = (expression.clone().then_ignore(just(Token::Terminate)))
-                .or(block_expression)
+                // Expressions ending with a block don't need a terminator, unless they are the block's tail.
+                .or(block_expression.then_ignore(none_of([Token::CurlyRight]).rewind()))
+                // An expression that isn't the last one in it's block is missing it's terminator. It's still parsed as
+                // a statement, so that the error points at where the terminator is missing and the rest of the block
+                // is parsed as usual.
+                .or(expression
+                    .clone()
+                    .then_ignore(none_of([Token::CurlyRight]).rewind())
+                    .validate(|expression: (Expression, Span), _, emit| {
+                        let end = expression.1.range.end;
+
+                        emit(ParseError::custom(
+                            Span {
+                                path: expression.1.path,
+                                range: end..end + 1,
+                                origin: SpanOrigin::Written,
+                            },
+                            String::from("expected `;` to separate expressions"),
+                        ));
+
+                        expression
+                    }))
                 .repeated()
                 .then(expression.clone().map(Box::new).or_not())
-                .delimited_by(Token::CurlyLeft, Token::CurlyRight)
+                .delimited_by(just(Token::CurlyLeft), just(Token::CurlyRight))
                 .map_with_span(|(expressions, tail), span: Span| {
                     (Expression::Block { expressions, tail }, span)
                 })
@@ -362,12 +447,35 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
                 .boxed()
                 .labelled("block");
 
+            // A condition or a scrutinee is followed by a block, so one which starts with a block is ambiguous with it. These
+            // must be wrapped in parentheses instead, such as `if ({ ...; condition }) { ... }`. They are still parsed
+            // as usual, so that the rest of the expression is parsed as it was meant to be.
+            let condition = just(Token::CurlyLeft)
+                .rewind()
+                .ignore_then(expression.clone())
+                .validate(|condition: (Expression, Span), _, emit| {
+                    emit(ParseError::custom(
+                        condition.1.clone(),
+                        String::from(
+                            "this can't start with a block, since it's followed by one. Wrap it in \
+                             parentheses",
+                        ),
+                    ));
+
+                    condition
+                })
+                // Otherwise the error would be dropped, in favor of parsing the same block without it.
+                .or(none_of([Token::CurlyLeft])
+                    .rewind()
+                    .ignore_then(expression.clone()))
+                .labelled("condition");
+
             let conditional = just(Token::If)
-                .ignore_then(expression.clone().then(block.clone()))
+                .ignore_then(condition.clone().then(block.clone()))
                 .then(
                     just(Token::Else)
                         .then(just(Token::If))
-                        .ignore_then(expression.clone().then(block.clone()))
+                        .ignore_then(condition.clone().then(block.clone()))
                         .repeated(),
                 )
                 .then(
@@ -390,9 +498,20 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
                 .boxed()
                 .labelled("conditional");
 
-            let basic_loop = just(Token::Loop)
-                .ignore_then(block.clone())
-                .map_with_span(|block, span: Span| (Expression::Loop(Box::new(block)), span))
+            let basic_loop = label
+                .then_ignore(just(Token::Specify))
+                .or_not()
+                .then_ignore(just(Token::Loop))
+                .then(block.clone())
+                .map_with_span(|(label, block), span: Span| {
+                    (
+                        Expression::Loop {
+                            label,
+                            body: Box::new(block),
+                        },
+                        span,
+                    )
+                })
                 .labelled("loop");
 
             let structure = just(Token::Structure)
@@ -402,7 +521,7 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
                         .clone()
                         .separated_by(just(Token::Separate))
                         .allow_trailing()
-                        .delimited_by(Token::CurlyLeft, Token::CurlyRight)
+                        .delimited_by(just(Token::CurlyLeft), just(Token::CurlyRight))
                         .recover_with(recovery::nested_delimiters(
                             Token::CurlyLeft,
                             Token::CurlyRight,
@@ -416,28 +535,121 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
                 .boxed()
                 .labelled("structure");
 
+            let enumeration = just(Token::Enumeration)
+                .ignore_then(name)
+                .then(
+                    name.then(
+                        data_type
+                            .clone()
+                            .separated_by(just(Token::Separate))
+                            .allow_trailing()
+                            .delimited_by(just(Token::Left), just(Token::Right))
+                            .or_not()
+                            .map(Option::unwrap_or_default),
+                    )
+                    .separated_by(just(Token::Separate))
+                    .allow_trailing()
+                    .delimited_by(just(Token::CurlyLeft), just(Token::CurlyRight))
+                    .recover_with(recovery::nested_delimiters(
+                        Token::CurlyLeft,
+                        Token::CurlyRight,
+                        [(Token::Left, Token::Right)],
+                        |_| Vec::new(),
+                    )),
+                )
+                .map_with_span(|(name, variants), span: Span| {
+                    (Expression::Enumeration { name, variants }, span)
+                })
+                .boxed()
+                .labelled("enumeration");
+
+            let arm = id
+                .then(
+                    binding
+                        .separated_by(just(Token::Separate))
+                        .allow_trailing()
+                        .delimited_by(just(Token::Left), just(Token::Right))
+                        .or_not()
+                        .map(Option::unwrap_or_default),
+                )
+                .then_ignore(just(Token::FatArrow))
+                .then(expression.clone())
+                .map(|((variant, bindings), body)| (variant, bindings, body))
+                .labelled("match arm");
+
+            let match_expression = just(Token::Match)
+                .ignore_then(condition)
+                .then(
+                    arm.separated_by(just(Token::Separate))
+                        .allow_trailing()
+                        .delimited_by(just(Token::CurlyLeft), just(Token::CurlyRight))
+                        .recover_with(recovery::nested_delimiters(
+                            Token::CurlyLeft,
+                            Token::CurlyRight,
+                            [(Token::Left, Token::Right)],
+                            |_| Vec::new(),
+                        )),
+                )
+                .map_with_span(|(scrutinee, arms), span: Span| {
+                    (
+                        Expression::Match {
+                            scrutinee: Box::new(scrutinee),
+                            arms,
+                        },
+                        span,
+                    )
+                })
+                .boxed()
+                .labelled("match");
+
+            // The instance an instance is based on may be given anywhere among it's fields, such as
+            // `Point { ..origin, x: 1 }`.
             let instance = id
                 .then(
-                    name.then_ignore(just(Token::Specify))
-                        .then(expression.clone())
+                    just(Token::Spread)
+                        .ignore_then(expression.clone())
+                        .map(InstanceElement::Base)
+                        .or(name
+                            .then_ignore(just(Token::Specify))
+                            .then(expression.clone())
+                            .map(|(name, value)| InstanceElement::Field(name, value)))
                         .separated_by(just(Token::Separate))
                         .allow_trailing()
-                        .delimited_by(Token::CurlyLeft, Token::CurlyRight)
+                        .delimited_by(just(Token::CurlyLeft), just(Token::CurlyRight))
                         .recover_with(recovery::nested_delimiters(
                             Token::CurlyLeft,
                             Token::CurlyRight,
                             [(Token::Left, Token::Right)],
                             |_| Vec::new(),
-                        ))
-                        .map(|fields| {
-                            fields
-                                .into_iter()
-                                .map(|(name, value)| (name, value))
-                                .collect::<Vec<_>>()
-                        }),
+                        )),
                 )
-                .map_with_span(|(object, fields), span: Span| {
-                    (Expression::Instance { object, fields }, span)
+                .validate(|(object, elements), span: Span, emit| {
+                    let mut fields = Vec::new();
+                    let mut base = None;
+
+                    for element in elements {
+                        match element {
+                            InstanceElement::Field(name, value) => fields.push((name, value)),
+                            InstanceElement::Base(value) if base.is_some() => {
+                                emit(ParseError::custom(
+                                    value.1,
+                                    String::from(
+                                        "an instance can only be based on one other instance",
+                                    ),
+                                ));
+                            }
+                            InstanceElement::Base(value) => base = Some(Box::new(value)),
+                        }
+                    }
+
+                    (
+                        Expression::Instance {
+                            object,
+                            fields,
+                            base,
+                        },
+                        span,
+                    )
                 })
                 .boxed()
                 .labelled("instance");
@@ -446,9 +658,10 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
                 .ignore_then(name)
                 .then(
                     field
+                        .clone()
                         .separated_by(just(Token::Separate))
                         .allow_trailing()
-                        .delimited_by(Token::Left, Token::Right)
+                        .delimited_by(just(Token::Left), just(Token::Right))
                         .recover_with(recovery::nested_delimiters(
                             Token::Left,
                             Token::Right,
@@ -456,39 +669,115 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
                             |_| Vec::new(),
                         )),
                 )
-                .then(just(Token::Arrow).ignore_then(data_type).or_not())
-                .then(block.clone())
-                .map_with_span(|(((name, parameters), return_type), body), span: Span| {
-                    (
-                        Expression::Function {
-                            name: TypeHint {
-                                value: name,
-                                type_hint: return_type,
+                .then(just(Token::Arrow).ignore_then(data_type.clone()).or_not())
+                // A function without a body is still parsed, so that the rest of the file is parsed as usual.
+                .then(block.clone().or_not())
+                .validate(
+                    |(((name, parameters), return_type), body), span: Span, emit| {
+                        let body = body.unwrap_or_else(|| {
+                            emit(ParseError::specific(
+                                Error::MissingFunctionBody {
+                                    function_span: span.clone(),
+                                },
+                                span.clone(),
+                            ));
+
+                            (Expression::Error, span.clone())
+                        });
+
+                        (
+                            Expression::Function {
+                                name: TypeHint {
+                                    value: name,
+                                    type_hint: return_type,
+                                },
+                                parameters,
+                                body: Box::new(body),
                             },
-                            parameters,
-                            body: Box::new(body),
-                        },
-                        span,
-                    )
-                })
+                            span,
+                        )
+                    },
+                )
                 .boxed()
                 .labelled("function");
 
             choice((
                 conditional,
+                match_expression,
                 function,
                 basic_loop,
                 structure,
+                enumeration,
                 instance,
                 block,
             ))
         })
         .boxed();
 
+        // Intrinsics are evaluated by the compiler itself, and all of them currently take a single value.
+        let intrinsic = just(Token::At)
+            .ignore_then(name)
+            .then(
+                expression
+                    .clone()
+                    .delimited_by(just(Token::Left), just(Token::Right)),
+            )
+            .validate(|((intrinsic, intrinsic_span), value), span: Span, emit| {
+                let value = Box::new(value);
+
+                match intrinsic.as_str() {
+                    "typeof" => (Expression::TypeOf(value), span),
+                    "assert" => (Expression::Assert(value), span),
+                    "dbg" => (Expression::Dbg(value), span),
+                    _ => {
+                        emit(ParseError::custom(
+                            intrinsic_span,
+                            format!("there is no intrinsic named `@{}`", intrinsic),
+                        ));
+
+                        (Expression::Error, span)
+                    }
+                }
+            })
+            .labelled("intrinsic");
+
+        // Closures are functions written inside of an expression, such as `|x: Int| -> Int { x + 1 }`. `||` is lexed as a
+        // single token, so it's also accepted as an empty parameter list.
+        let closure = field
+            .separated_by(just(Token::Separate))
+            .allow_trailing()
+            .delimited_by(just(Token::Pipe), just(Token::Pipe))
+            .or(just(Token::Or).to(Vec::new()))
+            .then(just(Token::Arrow).ignore_then(data_type).or_not())
+            .then(expression.clone())
+            .map_with_span(|((parameters, return_type), body), span: Span| {
+                (
+                    Expression::Function {
+                        name: TypeHint {
+                            value: (closure_name(&span), span.clone()),
+                            type_hint: return_type,
+                        },
+                        parameters,
+                        body: Box::new(body),
+                    },
+                    span,
+                )
+            })
+            .boxed()
+            .labelled("closure");
+
         let atom = choice((
+            // This must come before grouping, which would otherwise fail on the empty parentheses.
+            just(Token::Left)
+                .then(just(Token::Right))
+                .map_with_span(|_, span| (Expression::Unit, span)),
+            just(Token::Null).map_with_span(|_, span| (Expression::Null, span)),
+            // The span of a grouping covers it's parentheses, so that the span of any expression containing it covers the
+            // whole of it's text.
             expression
                 .clone()
-                .delimited_by(Token::Left, Token::Right)
+                .delimited_by(just(Token::Left), just(Token::Right))
+                .map_with_span(|(expression, _), span| (expression, span))
                 .recover_with(recovery::nested_delimiters(
                     Token::Left,
                     Token::Right,
@@ -499,6 +788,8 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
             boolean,
             pure_string,
             string,
+            intrinsic,
+            closure,
             id.map(|(id, span)| (Expression::Identifier(id), span)),
             block_expression.clone(),
         ))
@@ -510,50 +801,73 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
         ))
         .boxed();
 
-        let access = atom
+        // Field accesses and calls are both postfix, so they can follow each other in any order, such as in
+        // `foo().bar.baz()`.
+        let postfix = atom
             .clone()
-            .then(just(Token::Of).ignore_then(name).repeated())
-            .foldl(|left, right| {
+            .then(
+                just(Token::Of)
+                    .ignore_then(name)
+                    .map(Postfix::Access)
+                    .or(expression
+                        .clone()
+                        .separated_by(just(Token::Separate))
+                        .allow_trailing()
+                        .delimited_by(just(Token::Left), just(Token::Right))
+                        .recover_with(recovery::nested_delimiters(
+                            Token::Left,
+                            Token::Right,
+                            [(Token::CurlyLeft, Token::CurlyRight)],
+                            |_| Vec::new(),
+                        ))
+                        .map_with_span(|parameters, span| Postfix::Call((parameters, span))))
+                    .repeated(),
+            )
+            .foldl(|left: (Expression, Span), right| {
+                let end = match &right {
+                    Postfix::Access((_, span)) | Postfix::Call((_, span)) => span.range.end,
+                };
                 let span = Span {
-                    range: left.1.range.start..right.1.range.end,
+                    range: left.1.range.start..end,
                     path: left.1.path,
+                    origin: SpanOrigin::Written,
                 };
 
                 (
-                    Expression::Access {
-                        from: Box::new(left),
-                        field: right,
+                    match right {
+                        Postfix::Access(field) => Expression::Access {
+                            from: Box::new(left),
+                            field,
+                        },
+                        Postfix::Call(parameters) => Expression::Call {
+                            function: Box::new(left),
+                            parameters,
+                        },
                     },
                     span,
                 )
-            });
+            })
+            .boxed();
 
-        let call = access
-            .then(
-                expression
-                    .clone()
-                    .separated_by(just(Token::Separate))
-                    .allow_trailing()
-                    .delimited_by(Token::Left, Token::Right)
-                    .recover_with(recovery::nested_delimiters(
-                        Token::Left,
-                        Token::Right,
-                        [(Token::CurlyLeft, Token::CurlyRight)],
-                        |_| Vec::new(),
-                    ))
-                    .map_with_span(|expression, span| (expression, span))
-                    .repeated(),
-            )
-            .foldl(|left: (Expression, Span), right| {
+        // Prefix operators bind tighter than any binary operator, so `*a + 1` dereferences `a` before adding to it.
+        let unary = just(Token::Reference)
+            .to(true)
+            .or(just(Token::Multiply).to(false))
+            .map_with_span(|is_reference, span: Span| (is_reference, span))
+            .repeated()
+            .then(postfix)
+            .foldr(|(is_reference, span), value| {
                 let span = Span {
-                    range: left.1.range.start..right.1.range.end,
-                    path: left.1.path,
+                    range: span.range.start..value.1.range.end,
+                    path: span.path,
+                    origin: SpanOrigin::Written,
                 };
 
                 (
-                    Expression::Call {
-                        function: Box::new(left),
-                        parameters: right,
+                    if is_reference {
+                        Expression::Reference(Box::new(value))
+                    } else {
+                        Expression::Dereference(Box::new(value))
                     },
                     span,
                 )
@@ -561,7 +875,7 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
             .boxed();
 
         let product = binary_operation(
-            call,
+            unary,
             choice((
                 operator!(Token::Multiply, "multiply"),
                 operator!(Token::Divide, "divide"),
@@ -592,43 +906,63 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
             operator!(Token::Or, "or").or(operator!(Token::And, "and")),
         );
 
+        let assign_operator = just(Token::Assign).to(None).or(choice((
+            operator!(Token::AddAssign, "add"),
+            operator!(Token::SubtractAssign, "subtract"),
+            operator!(Token::MultiplyAssign, "multiply"),
+            operator!(Token::DivideAssign, "divide"),
+            operator!(Token::ModuloAssign, "modulo"),
+        ))
+        .map(|(operation, span)| Some((Id::new(vec![Intern::new(operation.to_string())]), span))));
+
         let assign = logic
             .clone()
-            .then(just(Token::Assign).ignore_then(logic).repeated())
+            .then(assign_operator.then(logic).repeated())
             .map(|(head, body)| {
-                body.into_iter()
+                // Assignment is right associative, so `a = b += c` assigns `b += c` to `a`. Each
+                // operator belongs to the assignment whose target is to the left of it.
+                let (operators, mut values): (Vec<_>, Vec<_>) = body.into_iter().unzip();
+                values.insert(0, head);
+
+                let last = values.pop().unwrap();
+
+                values
+                    .into_iter()
+                    .zip(operators)
                     .rev()
-                    .chain(iter::once(head))
-                    .reduce(|last, before| {
+                    .fold(last, |last, (before, operator)| {
                         let span = Span {
                             range: before.1.range.start..last.1.range.end,
                             path: before.1.path,
+                            origin: SpanOrigin::Written,
                         };
 
                         (
                             Expression::Assignment {
                                 to: Box::new(before),
                                 from: Box::new(last),
+                                operator,
                             },
                             span,
                         )
                     })
-                    .unwrap()
             })
             .boxed();
 
         let import = just(Token::Use)
             .ignore_then(id)
-            .map_with_span(|id, span| (Expression::Import(id), span))
+            .then(just(Token::As).ignore_then(name).or_not())
+            .map_with_span(|(path, alias), span| (Expression::Import { path, alias }, span))
             .boxed()
             .labelled("import");
 
         let declaration = just(Token::Variable)
-            .ignore_then(name)
+            .ignore_then(just(Token::Mutable).or_not())
+            .then(binding)
             .then(type_hint.or_not())
             .then_ignore(just(Token::Assign))
             .then(expression.clone())
-            .map_with_span(|((name, type_hint), value), span| {
+            .map_with_span(|(((mutable, name), type_hint), value), span| {
                 (
                     Expression::Declaration {
                         name: TypeHint {
@@ -636,6 +970,7 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
                             type_hint,
                         },
                         value: Box::new(value),
+                        mutable: mutable.is_some(),
                     },
                     span,
                 )
@@ -643,12 +978,22 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
             .boxed()
             .labelled("variable declaration");
 
-        let continue_flow =
-            just(Token::Continue).map_with_span(|_, span: Span| (Expression::Continue, span));
+        let continue_flow = just(Token::Continue)
+            .ignore_then(label.or_not())
+            .map_with_span(|label, span: Span| (Expression::Continue(label), span));
 
         let break_flow = just(Token::Break)
-            .ignore_then(expression.clone())
-            .map_with_span(|expression, span| (Expression::Break(Box::new(expression)), span));
+            .ignore_then(label.or_not())
+            .then(expression.clone())
+            .map_with_span(|(label, value), span| {
+                (
+                    Expression::Break {
+                        label,
+                        value: Box::new(value),
+                    },
+                    span,
+                )
+            });
 
         let return_flow = just(Token::Return)
             .ignore_then(expression)
@@ -666,29 +1011,130 @@ fn build_parser() -> impl Parser<Token, Vec<(Expression, Span)>, Error = ParseEr
         .labelled("expression")
     })
     .boxed()
+    // An empty file is an empty module, rather than a module missing it's expressions.
     .repeated()
-    .at_least(1)
 }
 
 pub fn parse(
+    path: Intern<Utf8PathBuf>,
     tokens: Vec<(MetaToken, Span)>,
     global_errors: &mut Errors,
 ) -> Vec<(Expression, Span)> {
-    let tokens = TokenIterator::new(tokens);
+    let tokens = TokenIterator::new(path, tokens);
     let eof_span = tokens.get_end_span(); // In case parsing fails, it's important that we cache the EOF.
 
     let (ast, errors) = build_parser().parse_recovery(tokens);
 
-    global_errors.extend(errors.into_iter().map(|error| Error::Unexpected {
-        expected: error.expected,
-        found: Element {
-            // If it's None, it must have encountered the end of file.
-            value: error.found.unwrap_or(Pattern::Construct("end of file")),
-            span: error.span,
-        },
-        reason: error.reason,
-        while_parsing: error.label,
-    }));
+    global_errors.extend(errors.into_iter().map(Error::from));
 
     ast.unwrap_or_else(|| vec![(Expression::Error, eof_span)])
 }
+
+#[cfg(test)]
+mod tests {
+    use camino::Utf8PathBuf;
+    use internment::Intern;
+
+    use crate::core::ast::Expression;
+    use crate::core::error::{Error, Errors};
+    use crate::core::span::{SourceMap, Span};
+    use crate::front_end::generate_ast;
+
+    // Parses a file on it's own, returning it's top-level expressions along with the errors of parsing it.
+    fn parse_file(src: &str) -> (Vec<(Expression, Span)>, Errors) {
+        let mut errors = Errors {
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            sources: SourceMap::new(),
+        };
+
+        let ast = generate_ast(
+            Intern::new(Utf8PathBuf::from("main.bell")),
+            src,
+            &mut errors,
+        );
+
+        (ast, errors)
+    }
+
+    #[test]
+    fn empty_and_whitespace_only_files_are_empty_modules() {
+        for src in ["", "  \n\t\n", "\r\n"] {
+            let (ast, errors) = parse_file(src);
+
+            assert!(ast.is_empty(), "{:?}", ast);
+            assert!(errors.is_empty(), "{:?}", errors.errors);
+        }
+    }
+
+    #[test]
+    fn functions_without_a_body_are_recovered_from() {
+        let (_, errors) = parse_file("func foo()\n\nfunc main() {\n    var = 1;\n}\n");
+
+        assert!(
+            matches!(
+                errors.errors.as_slice(),
+                [Error::MissingFunctionBody { .. }, later @ ..] if !later.is_empty()
+            ),
+            "{:?}",
+            errors.errors
+        );
+    }
+
+    // Writes a chain of calls and field accesses back out, so that it's nesting can be compared.
+    fn chain(expression: &Expression) -> String {
+        match expression {
+            Expression::Identifier(id) => id.to_string(),
+            Expression::Call { function, .. } => format!("{}()", chain(&function.0)),
+            Expression::Access { from, field } => format!("{}.{}", chain(&from.0), field.0),
+            _ => panic!("unexpected expression in chain: {:?}", expression),
+        }
+    }
+
+    #[test]
+    fn fields_of_call_results_are_chained() {
+        let (ast, errors) = parse_file("func main() { foo().bar.baz() }");
+
+        assert!(errors.is_empty(), "{:?}", errors.errors);
+
+        let tail = match &ast[0].0 {
+            Expression::Function { body, .. } => match &body.0 {
+                Expression::Block {
+                    tail: Some(tail), ..
+                } => &tail.0,
+                body => panic!("expected a block with a tail, found {:?}", body),
+            },
+            top_level => panic!("expected a function, found {:?}", top_level),
+        };
+
+        assert_eq!(chain(tail), "foo().bar.baz()");
+    }
+
+    #[test]
+    fn parenthesized_conditions_are_parsed() {
+        let (_, errors) = parse_file(
+            "func main() {\n    if (ready) {}\n    if ({ var count = 1; count == 1 }) {}\n}\n",
+        );
+
+        assert!(errors.is_empty(), "{:?}", errors.errors);
+    }
+
+    #[test]
+    fn conditions_starting_with_a_block_are_rejected() {
+        for src in [
+            "func main() {\n    if { ready } {}\n}\n",
+            "func main() {\n    match { value } {}\n}\n",
+        ] {
+            let (_, errors) = parse_file(src);
+
+            assert!(
+                matches!(
+                    errors.errors.as_slice(),
+                    [Error::BasicAt { message, .. }] if message.starts_with("this can't start with a block")
+                ),
+                "{:?}",
+                errors.errors
+            );
+        }
+    }
+}
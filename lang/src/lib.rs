@@ -1,99 +1,433 @@
+// Parse errors are returned by value from the combinators of the parser, so they can't be made smaller by boxing them.
+#![allow(clippy::result_large_err)]
+
+use crate::core::diagnostic::{Diagnostic, DiagnosticSpan};
+#[cfg(feature = "fs")]
 use crate::core::error::Error;
 use crate::core::error::Errors;
 use crate::core::span::SourceMap;
-use crate::core::types::Types;
+use crate::core::types::{Engine, StaticScopes, Types};
+#[cfg(feature = "fs")]
+use crate::middle_end::hir;
 use crate::middle_end::hir::ToHir;
+#[cfg(feature = "fs")]
 use std::borrow::Cow;
+#[cfg(feature = "fs")]
+use std::fs;
 
 use crate::core::ast;
 
 use crate::core::file::Entry;
+#[cfg(feature = "fs")]
+use crate::core::file::Layout;
 use camino::Utf8PathBuf;
+#[cfg(feature = "fs")]
+use front_end::cache::AstCache;
 use front_end::module;
 use internment::Intern;
+#[cfg(feature = "fs")]
+use middle_end::graph;
 use middle_end::{check::check, gather};
-use std::fs;
+#[cfg(feature = "fs")]
+use rayon::prelude::*;
+use serde::Serialize;
+#[cfg(feature = "fs")]
 use std::path::PathBuf;
 
 pub mod core;
 pub mod front_end;
 pub mod middle_end;
 
+// The version of the compiler.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+// The version of the language itself, which only changes along with what programs mean or the data packs generated from
+// them, so it may stay the same across several versions of the compiler.
+pub const LANGUAGE_VERSION: &str = "0.5";
+
 #[derive(Copy, Clone)]
 pub enum OptLevel {
     Debug,
     Release,
 }
 
+// Only executables need a `main` function, libraries are just checked.
+#[derive(Copy, Clone, PartialEq)]
+pub enum ProjectKind {
+    Executable,
+    Library,
+}
+
+// The names and paths a compilation interned for the first time. Interning is shared by the whole process, so anything an
+// earlier compilation already interned isn't counted again, and compilations running at the same time count each other's.
+#[derive(Copy, Clone, Debug)]
+pub struct CompileStats {
+    pub interned_names: usize,
+    pub interned_paths: usize,
+}
+
+impl CompileStats {
+    fn interned() -> Self {
+        Self {
+            interned_names: Intern::<String>::num_objects_interned(),
+            interned_paths: Intern::<Utf8PathBuf>::num_objects_interned(),
+        }
+    }
+
+    fn since(before: Self) -> Self {
+        let after = Self::interned();
+
+        Self {
+            interned_names: after.interned_names.saturating_sub(before.interned_names),
+            interned_paths: after.interned_paths.saturating_sub(before.interned_paths),
+        }
+    }
+}
+
+// Reads and parses the project or file at the path. Any errors found along the way are inserted into the errors, and
+// `None` is only returned if nothing could be parsed at all.
+#[cfg(feature = "fs")]
+fn parse_project(
+    path: PathBuf,
+    cache: Option<PathBuf>,
+    errors: &mut Errors,
+) -> Option<ast::Module> {
+    let path = match Utf8PathBuf::from_path_buf(path) {
+        Ok(path) => path,
+        Err(path) => {
+            return {
+                errors.insert_error(Error::Basic(format!(
+                    "the path {} is not encoded in UTF-8",
+                    path.to_string_lossy()
+                )));
+
+                None
+            };
+        }
+    };
+
+    let cache = match cache.map(Utf8PathBuf::from_path_buf).transpose() {
+        Ok(cache) => cache.map(AstCache::new),
+        Err(path) => {
+            return {
+                errors.insert_error(Error::Basic(format!(
+                    "the path {} is not encoded in UTF-8",
+                    path.to_string_lossy()
+                )));
+
+                None
+            };
+        }
+    };
+
+    let project = Layout::walk(path, errors)?;
+
+    // Only the files that failed to load here are read again while building the entries, which is where their errors are
+    // reported.
+    let (mut sources, _) = SourceMap::load_project(&project);
+
+    Entry::from(project, &mut sources, errors)
+        .map(|entry| module::from_cached(entry, cache.as_ref(), errors))
+}
+
+// The scopes are returned along with the types, since the names of structures are only known through them.
+fn check_module(
+    module: ast::Module,
+    kind: ProjectKind,
+    mut errors: Errors,
+) -> Result<(Types, StaticScopes, Errors), Errors> {
+    let prelude = front_end::generate_prelude(&mut errors).to_hir(&mut errors);
+    let module = module.to_hir(&mut errors);
+    let gathered = gather::gather(&module, &prelude, &mut errors);
+    let (types, scopes) = check(module, prelude, gathered, kind, &mut errors);
+
+    // The errors are returned on success too, since they may still contain warnings.
+    if errors.is_empty() {
+        Ok((types, scopes, errors))
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(feature = "fs")]
 #[allow(unused)]
-pub fn compile(path: PathBuf, optimizations: OptLevel) -> Result<Types, Errors> {
+pub fn compile(
+    path: PathBuf,
+    optimizations: OptLevel,
+    kind: ProjectKind,
+    // A directory to cache the ASTs of files in, so that unchanged files aren't parsed again on the next compilation.
+    cache: Option<PathBuf>,
+) -> (Result<(Types, Errors), Errors>, CompileStats) {
+    let before = CompileStats::interned();
     let mut errors = Errors {
         errors: Vec::new(),
+        warnings: Vec::new(),
         sources: SourceMap::new(),
     };
 
-    let module = if path.is_file() {
-        let path = match Utf8PathBuf::from_path_buf(path) {
-            Ok(path) => path,
-            Err(path) => {
-                return {
-                    errors.insert_error(Error::Basic(format!(
-                        "the path {} is not encoded in UTF-8",
-                        path.to_string_lossy()
-                    )));
-
-                    Err(errors)
-                };
-            }
-        };
+    let result = match parse_project(path, cache, &mut errors) {
+        Some(module) => {
+            check_module(module, kind, errors).map(|(types, _, errors)| (types, errors))
+        }
+        None => Err(errors),
+    };
+
+    (result, CompileStats::since(before))
+}
+
+// Checks a single file whose text is already in memory, without touching the filesystem, so that the compiler can be
+// embedded where there is none, such as in a playground running in the browser. The path only names the file in spans.
+pub fn check_source(
+    path: Utf8PathBuf,
+    text: &str,
+    kind: ProjectKind,
+) -> Result<(Types, Errors), Errors> {
+    check_text(path, text, kind).map(|(types, _, errors)| (types, errors))
+}
 
-        module::from(
-            Entry::File {
-                contents: match fs::read_to_string(&path) {
-                    Ok(contents) => contents,
-                    Err(error) => {
-                        return {
-                            errors.insert_error(Error::IO {
-                                error,
-                                action: Cow::from(format!("read file {}", path)),
-                            });
-
-                            Err(errors)
-                        };
-                    }
-                },
-                path: Intern::new(path),
-            },
-            &mut errors,
-        )
-    } else if let Some(entry) = Entry::from(
-        match Utf8PathBuf::from_path_buf(path) {
-            Ok(path) => path,
-            Err(path) => {
-                return {
-                    errors.insert_error(Error::Basic(format!(
-                        "the path {} is not encoded in UTF-8",
-                        path.to_string_lossy()
-                    )));
-                    Err(errors)
-                };
-            }
+fn check_text(
+    path: Utf8PathBuf,
+    text: &str,
+    kind: ProjectKind,
+) -> Result<(Types, StaticScopes, Errors), Errors> {
+    let mut errors = Errors {
+        errors: Vec::new(),
+        warnings: Vec::new(),
+        sources: SourceMap::new(),
+    };
+
+    let module = module::from(
+        Entry::File {
+            path: Intern::new(path),
+            contents: text.to_owned(),
         },
         &mut errors,
-    ) {
-        module::from(entry, &mut errors)
+    );
+
+    check_module(module, kind, errors)
+}
+
+// The type of a piece of code, as it was found by the checker.
+#[derive(Serialize)]
+pub struct TypedSpan {
+    span: DiagnosticSpan,
+    #[serde(rename = "type")]
+    data_type: String,
+}
+
+// Everything there is to know about compiling a single file, in a form that can be serialized for a frontend which isn't
+// written in Rust, such as the JavaScript of a playground. The types are only known if the file was checked successfully.
+#[derive(Serialize)]
+pub struct CompileResult {
+    pub success: bool,
+    types: Vec<TypedSpan>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl CompileResult {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+// Compiles a file given as text, which works without a filesystem, so it may be called from WebAssembly. The name is
+// used as the file's path, so it should have a stem, like `main.bell`.
+pub fn compile_string(name: &str, src: &str) -> CompileResult {
+    let (success, types, scopes, mut errors) =
+        match check_text(Utf8PathBuf::from(name), src, ProjectKind::Executable) {
+            Ok((types, scopes, errors)) => (true, types, scopes, errors),
+            Err(errors) => (false, Vec::new(), StaticScopes::new_empty(), errors),
+        };
+
+    errors.sort_by_span();
+
+    // The prelude is checked along with the file, but only the types of the file itself are of any use.
+    let engine = Engine::new_with_types(types);
+    let types = engine
+        .types
+        .iter()
+        .enumerate()
+        .filter_map(|(type_id, (_, span))| {
+            let span = span.as_ref().filter(|span| span.path.as_str() == name)?;
+
+            Some(TypedSpan {
+                span: DiagnosticSpan::from(span),
+                data_type: engine
+                    .into_concrete_ty(type_id, &|type_id| scopes.search_type_name_by_id(type_id))
+                    .to_string(),
+            })
+        })
+        .collect();
+
+    let Errors {
+        errors, warnings, ..
+    } = errors;
+
+    CompileResult {
+        success,
+        types,
+        // Warnings come first, like they do when the CLI prints them.
+        diagnostics: warnings
+            .into_iter()
+            .map(Diagnostic::from)
+            .chain(errors.into_iter().map(Diagnostic::from))
+            .collect(),
+    }
+}
+
+// Compiles several independent files or projects at once, in parallel. Each one gets it's own errors and sources, and
+// interning is already shared safely between threads, so nothing else is shared between them. The results are in the
+// same order as the paths.
+#[cfg(feature = "fs")]
+pub fn compile_many(
+    paths: &[PathBuf],
+    optimizations: OptLevel,
+    kind: ProjectKind,
+) -> Vec<Result<(Types, Errors), Errors>> {
+    paths
+        .par_iter()
+        .map(|path| compile(path.clone(), optimizations, kind, None).0)
+        .collect()
+}
+
+// Lowers a project to it's HIR without checking it, which is useful for seeing how imports are resolved and how
+// expressions are desugared.
+#[cfg(feature = "fs")]
+pub fn lower(path: PathBuf, cache: Option<PathBuf>) -> Result<hir::Module, Errors> {
+    let mut errors = Errors {
+        errors: Vec::new(),
+        warnings: Vec::new(),
+        sources: SourceMap::new(),
+    };
+
+    let module = match parse_project(path, cache, &mut errors) {
+        Some(module) => module.to_hir(&mut errors),
+        None => return Err(errors),
+    };
+
+    if errors.is_empty() {
+        Ok(module)
     } else {
-        return Err(errors);
+        Err(errors)
+    }
+}
+
+// Prints the scopes of a project as they are after gathering, which is useful for seeing why a name does or doesn't
+// resolve.
+#[cfg(feature = "fs")]
+pub fn dump_scopes(path: PathBuf, cache: Option<PathBuf>) -> Result<String, Errors> {
+    let mut errors = Errors {
+        errors: Vec::new(),
+        warnings: Vec::new(),
+        sources: SourceMap::new(),
     };
 
-    let types = check(
-        gather::gather(module.to_hir(&mut errors), &mut errors),
-        &mut errors,
-    );
+    let module = match parse_project(path, cache, &mut errors) {
+        Some(module) => module,
+        None => return Err(errors),
+    };
+
+    let prelude = front_end::generate_prelude(&mut errors).to_hir(&mut errors);
+    let module = module.to_hir(&mut errors);
+    let gathered = gather::gather(&module, &prelude, &mut errors);
 
     if errors.is_empty() {
-        Ok(types)
+        Ok(gathered.scopes.dump())
     } else {
         Err(errors)
     }
 }
+
+// Renders the modules of a project and the imports between them as a Graphviz DOT graph.
+#[cfg(feature = "fs")]
+pub fn module_graph(path: PathBuf, cache: Option<PathBuf>) -> Result<String, Errors> {
+    lower(path, cache).map(|module| graph::module_graph(&module))
+}
+
+// Formats a single file. Only the file itself is parsed, so it may be formatted even if the project it is in has errors.
+#[cfg(feature = "fs")]
+pub fn format(path: PathBuf) -> Result<String, Errors> {
+    let mut errors = Errors {
+        errors: Vec::new(),
+        warnings: Vec::new(),
+        sources: SourceMap::new(),
+    };
+
+    let path = match Utf8PathBuf::from_path_buf(path) {
+        Ok(path) => path,
+        Err(path) => {
+            return {
+                errors.insert_error(Error::Basic(format!(
+                    "the path {} is not encoded in UTF-8",
+                    path.to_string_lossy()
+                )));
+
+                Err(errors)
+            };
+        }
+    };
+
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(error) => {
+            return {
+                errors.insert_error(Error::IO {
+                    error,
+                    action: Cow::from(format!("read file {}", path)),
+                });
+
+                Err(errors)
+            };
+        }
+    };
+
+    format_source(path, &text)
+}
+
+// Formats text that is already in memory, with the path naming it in errors.
+pub fn format_source(path: Utf8PathBuf, text: &str) -> Result<String, Errors> {
+    let mut errors = Errors {
+        errors: Vec::new(),
+        warnings: Vec::new(),
+        sources: SourceMap::new(),
+    };
+
+    let path = Intern::new(path);
+    let text = text.to_owned();
+    let ast = front_end::generate_ast(path, &text, &mut errors);
+
+    if errors.is_empty() {
+        Ok(front_end::format::format(&text, &ast))
+    } else {
+        errors.insert_source(path, text);
+
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compile_string;
+
+    #[test]
+    fn instances_are_typed_by_their_structure() {
+        let result = compile_string(
+            "main.bell",
+            "struct Point { x: Int }\nfunc main() { var point = Point { x: 1 }; }\n",
+        );
+
+        assert!(result.success);
+        assert!(result.types.iter().any(|typed| typed.data_type == "Point"));
+    }
+
+    #[test]
+    fn only_the_types_of_the_file_are_given() {
+        let result = compile_string("main.bell", "func main() { var value = abs(-1); }\n");
+
+        assert!(result.success);
+        assert!(!result.types.is_empty());
+        assert!(result
+            .types
+            .iter()
+            .all(|typed| typed.span.path == "main.bell"));
+    }
+}
@@ -0,0 +1,65 @@
+use crate::core::{ast::IntegerWidth, types::Type};
+
+// Builtins are functions provided by the compiler itself, operators included, since they are parsed into calls to
+// functions such as `add`. A name only refers to a builtin if the program doesn't declare anything by that name. Every
+// builtin is defined here, so that the checker and anything listing them can't disagree on what they are.
+//
+// Builtins may be generic over a single type, written as an unknown. Every unknown in a signature is the same type, which
+// is inferred separately for each use of the builtin.
+pub fn builtins() -> Vec<(&'static str, Type)> {
+    let function = |parameters, return_type| Type::Function {
+        parameters,
+        return_type: Box::new(return_type),
+    };
+    let arithmetic = || function(vec![Type::Integer, Type::Integer], Type::Integer);
+    let comparison = || function(vec![Type::Integer, Type::Integer], Type::Boolean);
+    let logic = || function(vec![Type::Boolean, Type::Boolean], Type::Boolean);
+    let equality = || function(vec![Type::Unknown, Type::Unknown], Type::Boolean);
+
+    vec![
+        ("add", arithmetic()),
+        ("subtract", arithmetic()),
+        ("multiply", arithmetic()),
+        ("divide", arithmetic()),
+        ("modulo", arithmetic()),
+        ("lesser", comparison()),
+        ("greater", comparison()),
+        ("lesser_or_equal", comparison()),
+        ("greater_or_equal", comparison()),
+        ("equal", equality()),
+        ("not_equal", equality()),
+        ("and", logic()),
+        ("or", logic()),
+        ("to_string", function(vec![Type::Unknown], Type::String)),
+        // Interpolated strings are desugared into concatenations of their parts.
+        (
+            "concat",
+            function(vec![Type::String, Type::String], Type::String),
+        ),
+        ("len", function(vec![Type::String], Type::Integer)),
+        // Conversions take any integer, which isn't a type of it's own, so they are generic. See `CONVERSIONS`.
+        ("to_int", function(vec![Type::Unknown], Type::Integer)),
+        (
+            "to_i8",
+            function(vec![Type::Unknown], Type::SizedInteger(IntegerWidth::I8)),
+        ),
+        (
+            "to_i16",
+            function(vec![Type::Unknown], Type::SizedInteger(IntegerWidth::I16)),
+        ),
+        (
+            "to_i64",
+            function(vec![Type::Unknown], Type::SizedInteger(IntegerWidth::I64)),
+        ),
+    ]
+}
+
+// The builtins converting between integer types, whose argument is only checked to be an integer once it's inferred.
+pub const CONVERSIONS: &[&str] = &["to_int", "to_i8", "to_i16", "to_i64"];
+
+pub fn search_builtin(name: &str) -> Option<Type> {
+    builtins()
+        .into_iter()
+        .find(|&(builtin, _)| builtin == name)
+        .map(|(_, data_type)| data_type)
+}
@@ -1,31 +1,74 @@
 use std::collections::HashMap;
+use std::iter;
 
+use crate::core::error::{Backtrace, Element, Error, OptElement, TraceElement, Warning};
 use crate::core::{
     error::Errors,
+    progress,
     types::{
-        Constraint, Engine, Fields, IntoTyInfo, LinkReason, Mismatch, ScopeContext, StaticScopes,
-        Type, TypeId, Types, Variable,
+        Constraint, Engine, IntoTyInfo, LinkReason, Mismatch, ScopeContext, StaticScopes, Type,
+        TypeId, Types, Variable,
     },
+    Name,
 };
 use crate::core::{
-    error::{Backtrace, Element, Error, OptElement, TraceElement},
-    types::Scopes,
+    span::Span,
+    types::{TypeInfo, Unknown},
 };
-use crate::core::{span::Span, types::TypeInfo};
 use crate::{
-    core::ast::{Id, TypeHint},
+    core::ast::{is_closure_name, Id, TypeHint, PRIMITIVE_TYPES},
     middle_end::hir::AssignLocation,
 };
 
-use crate::middle_end::hir::{Expression, Function, Structure, TopLevel};
+use crate::middle_end::hir::{Arm, Enumeration, Expression, Function, Structure, TopLevel};
+
+use crate::ProjectKind;
 
 use super::{
+    builtin::{search_builtin, CONVERSIONS},
+    constant::{self, Constant},
     gather::GatherOut,
     hir::{Module, Program},
 };
 
+// The `main` function of the entry module, which is only searched for in executables.
+struct EntryPoint {
+    span: Span,
+    function_type: Option<TypeId>,
+}
+
+// The types a function was declared with, before it's body was checked.
+struct Signature {
+    parameters: Vec<TypeId>,
+    return_type: TypeId,
+    function_type: TypeId,
+}
+
+// A match can only be checked for exhaustiveness once the type of it's scrutinee is known.
+struct MatchCheck {
+    scrutinee: TypeId,
+    covered: Vec<Name>,
+    span: Span,
+}
+
 pub struct Checker<'a> {
     constraints: Vec<Constraint>,
+    matches: Vec<MatchCheck>,
+    // The labeled loops surrounding the current expression, with their return types.
+    labels: Vec<(Name, TypeId)>,
+    entry_point: Option<EntryPoint>,
+    // Every type id from this one onward was created while checking, so variables with them are locals. Anything before
+    // it was gathered, and is global.
+    first_local: TypeId,
+    // The first type id of the innermost nested function and it's span. Locals with type ids before it belong to the
+    // functions surrounding it, which nested functions can't capture.
+    nested_function: Option<(TypeId, Span)>,
+    // The values of every `@typeof`, which are only resolved once everything was inferred.
+    type_queries: Vec<(TypeId, Span)>,
+    // The values of every `@dbg`, which can only be printed if they are of a type that is known to be printable.
+    debug_prints: Vec<(TypeId, Span)>,
+    // The arguments of every conversion between integer types, which are only known to be integers once inferred.
+    conversions: Vec<(TypeId, Span)>,
     scopes: StaticScopes,
     engine: Engine,
     errors: &'a mut Errors,
@@ -37,19 +80,64 @@ because all type ids that are being used should be guaranteed to have a span,
 as all type ids without a span are generally only used for a single unification.
 */
 impl<'a> Checker<'a> {
-    fn check(mut self, root_module: Module) -> Types {
+    fn check(
+        mut self,
+        root_module: Module,
+        prelude: Program,
+        kind: ProjectKind,
+    ) -> (Types, StaticScopes) {
+        if kind == ProjectKind::Executable {
+            self.entry_point = search_main(&root_module).map(|span| EntryPoint {
+                span,
+                function_type: None,
+            });
+
+            if self.entry_point.is_none() {
+                self.errors.insert_error(Error::MissingMain);
+            }
+        }
+
+        // The prelude was gathered into the root scope before any module, so it's checked in the same order.
+        self.check_program(prelude);
         self.check_module(root_module);
         self.solve_constraints();
+        self.check_exhaustiveness();
+        self.check_entry_point();
+        self.resolve_type_queries();
+        self.check_debug_prints();
+        self.check_conversions();
 
         for Mismatch { a, b, reason } in self.engine.mismatches.iter().copied() {
-            self.errors.insert_error(Error::TypeMismatch {
-                a: self.collect_trace(a),
-                b: self.collect_trace(b),
-                reason,
-            })
+            if let Some((optional, span)) = self.search_unwrapped_optional(a, b) {
+                self.errors.insert_error(Error::UnwrappedOptional(Element {
+                    value: self.into_concrete_ty(optional),
+                    span,
+                }));
+            } else if let Some(error) = self.search_argument_count_mismatch(a, b, reason) {
+                self.errors.insert_error(error);
+            } else {
+                self.errors.insert_error(Error::TypeMismatch {
+                    a: self.collect_trace(a),
+                    b: self.collect_trace(b),
+                    reason,
+                })
+            }
+        }
+
+        // The span of the type containing the unknown is used when there is one, since it's where the cycle shows up.
+        for &(variable, type_id) in &self.engine.infinite {
+            if let Some(span) = self
+                .engine
+                .get(type_id)
+                .1
+                .clone()
+                .or_else(|| self.engine.get(variable).1.clone())
+            {
+                self.errors.insert_error(Error::InfiniteType { span });
+            }
         }
 
-        self.engine.types
+        (self.engine.types, self.scopes)
     }
 
     fn check_module(&mut self, module: Module) {
@@ -72,11 +160,21 @@ impl<'a> Checker<'a> {
         expression: (Expression, Span),
         context: ScopeContext,
     ) -> TypeId {
+        progress::at(&expression.1);
+
         match expression.0 {
             Expression::Unit => self.engine.insert_type(TypeInfo::Unit, Some(expression.1)),
+            Expression::Null => {
+                let inner = self
+                    .engine
+                    .insert_type(TypeInfo::Unknown(Unknown::Inferable), None);
+
+                self.engine
+                    .insert_type(TypeInfo::Optional(inner), Some(expression.1))
+            }
             Expression::Int(_) => self
                 .engine
-                .insert_type(TypeInfo::Integer, Some(expression.1)),
+                .insert_type(TypeInfo::Unknown(Unknown::Integer), Some(expression.1)),
             Expression::Boolean(_) => self
                 .engine
                 .insert_type(TypeInfo::Boolean, Some(expression.1)),
@@ -84,14 +182,33 @@ impl<'a> Checker<'a> {
                 .engine
                 .insert_type(TypeInfo::String, Some(expression.1)),
             Expression::Id(id) => {
-                if let Some(symbol) = self.search_id(&id) {
+                if let Some(type_id) = self.search_id(&id).map(|symbol| symbol.type_id) {
+                    if let Some(function) = self.search_capture(type_id) {
+                        self.errors.insert_error(Error::CannotCaptureEnvironment {
+                            variable: Element {
+                                value: id.leaf(),
+                                span: expression.1.clone(),
+                            },
+                            function,
+                        });
+
+                        return self
+                            .engine
+                            .insert_type(TypeInfo::Unknown(Unknown::Errored), Some(expression.1));
+                    }
+
                     self.engine.insert_type(
                         TypeInfo::Link {
-                            linked_to: symbol.type_id,
+                            linked_to: type_id,
                             reason: LinkReason::Other,
                         },
                         Some(expression.1),
                     )
+                } else if let Some(builtin) = id
+                    .as_single()
+                    .and_then(|name| self.builtin_type(name, &expression.1))
+                {
+                    builtin
                 } else {
                     self.errors.insert_error(Error::MissingId {
                         id: Element {
@@ -100,42 +217,81 @@ impl<'a> Checker<'a> {
                         },
                     });
                     self.engine
-                        .insert_type(TypeInfo::Unknown(true), Some(expression.1))
+                        .insert_type(TypeInfo::Unknown(Unknown::Errored), Some(expression.1))
                 }
             }
             Expression::Function(function) => self.check_function(function, expression.1),
-            Expression::Instance { object, fields } => {
-                if let Some(symbol) = self.search_id(&object.0) {
-                    let mut field_types: HashMap<Id, (TypeId, Span)> =
+            Expression::Instance {
+                object,
+                fields,
+                base,
+            } => {
+                if let Some(structure) = self.search_id(&object.0).map(|symbol| symbol.type_id) {
+                    let mut field_types: HashMap<Name, (TypeId, Span)> =
                         HashMap::with_capacity(fields.len());
 
                     for (id, value) in fields {
                         if let Some((_, other_span, ..)) = field_types.get(&id.0) {
-                            self.errors.insert_error(Error::ConflictingIds {
-                                first: other_span.clone(),
-                                second: id.1,
-                                id: Id::new_single(id.0),
-                            })
+                            self.errors.insert_error(Error::conflicting_ids(
+                                other_span.clone(),
+                                id.1,
+                                Id::new_single(id.0),
+                            ))
                         } else {
                             field_types.insert(id.0, (self.check_expression(value, context), id.1));
                         }
                     }
 
-                    let given_type = self.engine.insert_type(
-                        TypeInfo::Structure(
-                            field_types
-                                .into_iter()
-                                .map(|(field, (type_id, _))| (field, type_id))
-                                .collect(),
-                        ),
-                        Some(expression.1.clone()),
-                    );
+                    // The fields that aren't given are taken from the base, so none of them are missing. It must be an
+                    // instance of the same structure.
+                    if let Some(base) = base {
+                        let base_type = self.check_expression(*base, context);
+                        let based_on = self.engine.insert_type(
+                            TypeInfo::Instance {
+                                structure,
+                                origin: None,
+                            },
+                            Some(expression.1.clone()),
+                        );
 
-                    self.engine
-                        .unify(symbol.type_id, given_type, LinkReason::Structure);
+                        self.engine
+                            .unify(based_on, base_type, LinkReason::Structure);
 
-                    self.engine
-                        .insert_type(TypeInfo::Instance(symbol.type_id), Some(expression.1))
+                        // The structure may be declared after this, so the fields it has aren't known yet. Each given
+                        // field is checked like an access of the instance, once the structure is resolved.
+                        for (field, (type_id, span)) in field_types {
+                            let field_id = self
+                                .engine
+                                .insert_type(TypeInfo::Unknown(Unknown::Inferable), Some(span));
+
+                            self.engine.unify(field_id, type_id, LinkReason::Field);
+                            self.insert_constraint(Constraint::new(based_on, field_id, field));
+                        }
+                    } else {
+                        // The structure may be declared after this, so the fields it has aren't known yet. The given
+                        // fields are checked against them once it's resolved.
+                        let mut fields = field_types
+                            .into_iter()
+                            .map(|(field, (type_id, span))| (field, type_id, span))
+                            .collect::<Vec<_>>();
+
+                        fields.sort_by_key(|(_, _, span)| span.range.start);
+
+                        self.insert_constraint(Constraint::Instance {
+                            object: object.0,
+                            structure,
+                            fields,
+                            span: expression.1.clone(),
+                        });
+                    }
+
+                    self.engine.insert_type(
+                        TypeInfo::Instance {
+                            structure,
+                            origin: Some(expression.1.clone()),
+                        },
+                        Some(expression.1),
+                    )
                 } else {
                     self.errors.insert_error(Error::MissingId {
                         id: Element {
@@ -145,26 +301,60 @@ impl<'a> Checker<'a> {
                     });
 
                     self.engine
-                        .insert_type(TypeInfo::Unknown(true), Some(expression.1))
+                        .insert_type(TypeInfo::Unknown(Unknown::Errored), Some(expression.1))
                 }
             }
             Expression::Call {
                 function,
                 parameters,
             } => {
-                let parameter_types = parameters
+                self.check_builtin_call(&function, &parameters.0);
+                self.check_overflow(&function, &parameters.0, &expression.1);
+
+                let converts = match &function.0 {
+                    Expression::Id(id) => id.as_single().is_some_and(|name| {
+                        CONVERSIONS.contains(&name.as_str())
+                            && self.scopes.search_variable(name).is_none()
+                    }),
+                    _ => false,
+                };
+
+                let constants = parameters
+                    .0
+                    .iter()
+                    .map(constant_integer)
+                    .collect::<Vec<_>>();
+                let arguments = parameters
                     .0
                     .into_iter()
                     .map(|parameter| self.check_expression(parameter, context))
-                    .collect();
+                    .collect::<Vec<_>>();
 
-                let return_type = self
-                    .engine
-                    .insert_type(TypeInfo::Unknown(false), Some(expression.1.clone()));
+                if let (true, &[argument]) = (converts, &arguments[..]) {
+                    let span = self.engine.get(argument).1.clone().unwrap();
+                    self.conversions.push((argument, span));
+                }
+
+                // The arguments are widened into the parameters rather than unified with them, so that a narrower
+                // integer can be passed where a wider one is expected.
+                let parameter_types = arguments
+                    .iter()
+                    .map(|&argument| {
+                        let span = self.engine.get(argument).1.clone();
+
+                        self.engine
+                            .insert_type(TypeInfo::Unknown(Unknown::Inferable), span)
+                    })
+                    .collect::<Vec<_>>();
+
+                let return_type = self.engine.insert_type(
+                    TypeInfo::Unknown(Unknown::Inferable),
+                    Some(expression.1.clone()),
+                );
 
                 let expected_type = self.engine.insert_type(
                     TypeInfo::Function {
-                        parameters: parameter_types,
+                        parameters: parameter_types.clone(),
                         return_type,
                     },
                     Some(parameters.1),
@@ -174,73 +364,186 @@ impl<'a> Checker<'a> {
                 self.engine
                     .unify(found_type, expected_type, LinkReason::Call);
 
+                for (argument, parameter) in arguments.into_iter().zip(parameter_types) {
+                    self.engine
+                        .widen(argument, parameter, LinkReason::Parameter);
+                }
+
+                let parameters = match self.engine.get_resolved(found_type) {
+                    TypeInfo::Function { parameters, .. } => parameters.clone(),
+                    _ => Vec::new(),
+                };
+
+                for (constant, parameter) in constants.into_iter().zip(parameters) {
+                    self.check_range(constant, parameter);
+                }
+
+                return_type
+            }
+            Expression::MethodCall {
+                receiver,
+                method,
+                parameters,
+            } => {
+                let receiver_id = self.check_expression(*receiver, context);
+                let parameter_types = parameters
+                    .0
+                    .into_iter()
+                    .map(|parameter| self.check_expression(parameter, context))
+                    .collect::<Vec<_>>();
+
+                let return_type = self.engine.insert_type(
+                    TypeInfo::Unknown(Unknown::Inferable),
+                    Some(expression.1.clone()),
+                );
+
+                let field_call = self.engine.insert_type(
+                    TypeInfo::Function {
+                        parameters: parameter_types.clone(),
+                        return_type,
+                    },
+                    Some(method.1),
+                );
+                let free_call = self.engine.insert_type(
+                    TypeInfo::Function {
+                        parameters: iter::once(receiver_id).chain(parameter_types).collect(),
+                        return_type,
+                    },
+                    Some(parameters.1),
+                );
+
+                // The free function must be searched for now, since the scopes will have changed by the time the constraint is solved.
+                let free_function = self
+                    .search_id(&Id::new_single(method.0))
+                    .map(|variable| variable.type_id);
+
+                self.insert_constraint(Constraint::Method {
+                    receiver_id,
+                    method: method.0,
+                    field_call,
+                    free_call,
+                    free_function,
+                });
+
                 return_type
             }
-            Expression::Declaration { name, value } => {
+            Expression::Declaration {
+                name,
+                value,
+                mutable,
+            } => {
+                let constant = constant_integer(&value);
                 let expected_type = self.check_expression(*value, context);
-                let found_type = self
-                    .engine
-                    .insert_type(name.type_hint.into_ty(&self.scopes), Some(name.value.1));
+                self.check_function_shadowing(name.value.clone());
+                self.check_reserved_name(name.value.0, &name.value.1);
+
+                let type_hint = name
+                    .type_hint
+                    .into_ty(&self.scopes, &mut self.engine, self.errors);
+                let found_type = self.engine.insert_type(type_hint, Some(name.value.1));
 
                 self.engine
-                    .unify(expected_type, found_type, LinkReason::Other);
+                    .coerce(expected_type, found_type, LinkReason::Other);
+                self.check_range(constant, found_type);
 
                 self.scopes.insert_variable(
-                    name.value,
+                    name.value.0,
                     Variable {
                         type_id: found_type,
                         shadowable: true,
+                        mutable,
                     },
                 );
 
                 self.engine.insert_type(TypeInfo::Unit, Some(expression.1))
             }
             Expression::Assignment { to, from } => {
+                let constant = constant_integer(&from);
                 let expected_type = self.check_expression(*from, context);
                 let found_type = match to.0 {
                     AssignLocation::Variable(id) => {
-                        if let Some(symbol) = self.search_id(&id) {
-                            self.engine
-                                .unify(symbol.type_id, expected_type, LinkReason::Assign);
-
-                            symbol.type_id
+                        if let Some(&Variable {
+                            type_id, mutable, ..
+                        }) = self.search_id(&Id::new_single(id))
+                        {
+                            if !mutable {
+                                self.errors.insert_error(Error::AssignToImmutable {
+                                    name: Element {
+                                        value: id,
+                                        span: to.1.clone(),
+                                    },
+                                    declared_at: self.engine.get(type_id).1.clone(),
+                                });
+                            }
+
+                            if let Some(function) = self.search_capture(type_id) {
+                                self.errors.insert_error(Error::CannotCaptureEnvironment {
+                                    variable: Element {
+                                        value: id,
+                                        span: to.1.clone(),
+                                    },
+                                    function,
+                                });
+                            }
+
+                            type_id
                         } else {
                             self.errors.insert_error(Error::MissingId {
                                 id: Element {
-                                    value: id,
+                                    value: Id::new_single(id),
                                     span: to.1.clone(),
                                 },
                             });
 
-                            self.engine.insert_type(TypeInfo::Unknown(true), Some(to.1))
+                            self.engine
+                                .insert_type(TypeInfo::Unknown(Unknown::Errored), Some(to.1))
                         }
                     }
                     AssignLocation::Field { instance, field } => {
                         let object_id = self.check_expression(*instance, context);
                         let field_id = self
                             .engine
-                            .insert_type(TypeInfo::Unknown(false), Some(field.1));
+                            .insert_type(TypeInfo::Unknown(Unknown::Inferable), Some(field.1));
 
                         self.insert_constraint(Constraint::new(object_id, field_id, field.0));
 
                         field_id
                     }
+                    AssignLocation::Dereference(reference) => {
+                        let span = reference.1.clone();
+                        let reference = self.check_expression(*reference, context);
+
+                        self.dereference(reference, span)
+                    }
                 };
 
                 self.engine
-                    .unify(found_type, expected_type, LinkReason::Assign);
+                    .coerce(expected_type, found_type, LinkReason::Assign);
+                self.check_range(constant, found_type);
+
                 self.engine.insert_type(TypeInfo::Unit, Some(expression.1))
             }
             Expression::Access { from, id } => {
                 let expression = self.check_expression(*from, context);
                 let field_id = self
                     .engine
-                    .insert_type(TypeInfo::Unknown(false), Some(id.1));
+                    .insert_type(TypeInfo::Unknown(Unknown::Inferable), Some(id.1));
 
                 self.insert_constraint(Constraint::new(expression, field_id, id.0));
 
                 field_id
             }
+            Expression::Reference(value) => {
+                let value = self.check_expression(*value, context);
+
+                self.engine
+                    .insert_type(TypeInfo::Reference(value), Some(expression.1))
+            }
+            Expression::Dereference(reference) => {
+                let reference = self.check_expression(*reference, context);
+
+                self.dereference(reference, expression.1)
+            }
             Expression::Block { expressions, tail } => {
                 self.enter_scope();
 
@@ -254,12 +557,127 @@ impl<'a> Checker<'a> {
                 result
             }
             Expression::Structure(structure) => self.check_structure(structure, expression.1),
+            Expression::Enumeration(enumeration) => {
+                self.check_enumeration(enumeration, expression.1)
+            }
+            Expression::Match { scrutinee, arms } => {
+                let scrutinee = self.check_expression(*scrutinee, context);
+                let result = self.engine.insert_type(
+                    TypeInfo::Unknown(Unknown::Inferable),
+                    Some(expression.1.clone()),
+                );
+                let mut covered = Vec::with_capacity(arms.len());
+
+                for Arm {
+                    variant,
+                    bindings,
+                    body,
+                } in arms
+                {
+                    self.enter_scope();
+
+                    if let Some(constructor) = self.search_id(&variant.0) {
+                        let constructor = constructor.type_id;
+
+                        // Variants with a payload are constructed using a function, so the pattern is unified with one.
+                        let instance = if bindings.is_empty() {
+                            constructor
+                        } else {
+                            let parameters = bindings
+                                .into_iter()
+                                .map(|(name, span)| {
+                                    self.check_reserved_name(name, &span);
+
+                                    let type_id = self.engine.insert_type(
+                                        TypeInfo::Unknown(Unknown::Inferable),
+                                        Some(span),
+                                    );
+
+                                    self.scopes.insert_variable(
+                                        name,
+                                        Variable {
+                                            type_id,
+                                            shadowable: true,
+                                            mutable: false,
+                                        },
+                                    );
+
+                                    type_id
+                                })
+                                .collect();
+
+                            let instance = self.engine.insert_type(
+                                TypeInfo::Unknown(Unknown::Inferable),
+                                Some(variant.1.clone()),
+                            );
+                            let pattern = self.engine.insert_type(
+                                TypeInfo::Function {
+                                    parameters,
+                                    return_type: instance,
+                                },
+                                Some(variant.1.clone()),
+                            );
+
+                            self.engine
+                                .unify(constructor, pattern, LinkReason::Pattern);
+
+                            instance
+                        };
+
+                        self.engine
+                            .unify(scrutinee, instance, LinkReason::Pattern);
+                        covered.push(variant.0.leaf());
+                    } else {
+                        self.errors.insert_error(Error::MissingId {
+                            id: Element {
+                                value: variant.0,
+                                span: variant.1,
+                            },
+                        });
+                    }
+
+                    let body = self.check_expression(body, context);
+                    self.engine.unify(result, body, LinkReason::Match);
+
+                    self.exit_scope();
+                }
+
+                self.matches.push(MatchCheck {
+                    scrutinee,
+                    covered,
+                    span: expression.1,
+                });
+
+                result
+            }
             Expression::Conditional {
                 condition,
                 success,
                 failure,
             } => {
-                let boolean = self.engine.insert_type(TypeInfo::Boolean, None);
+                let constant = self.evaluate_constant(&condition);
+
+                if let Some(Constant::Boolean(value)) = constant {
+                    self.errors.insert_warning(Warning::ConstantCondition {
+                        span: condition.1.clone(),
+                        value,
+                    });
+
+                    // A conditional without an `else` is given an empty one covering the first branch, which isn't
+                    // something the user can remove.
+                    if value && failure.1.range != success.1.range {
+                        self.errors.insert_warning(Warning::UnreachableBranch {
+                            span: failure.1.clone(),
+                        });
+                    }
+                }
+
+                // Both branches are still checked when the condition is constant, since the code in them must be
+                // valid even if it never runs.
+                // The boolean is given the condition's span, so that a mismatch always points at the condition.
+                let boolean = self
+                    .engine
+                    .insert_type(TypeInfo::Boolean, Some(condition.1.clone()));
                 let condition = self.check_expression(*condition, context);
 
                 self.engine.unify(condition, boolean, LinkReason::Condition);
@@ -271,21 +689,74 @@ impl<'a> Checker<'a> {
 
                 success
             }
-            Expression::Break(expression) => {
+            Expression::ConditionalBinding {
+                name,
+                value,
+                mutable,
+                success,
+                failure,
+            } => {
+                let value = self.check_expression(*value, context);
+                let type_hint = name
+                    .type_hint
+                    .into_ty(&self.scopes, &mut self.engine, self.errors);
+                let inner = self
+                    .engine
+                    .insert_type(type_hint, Some(name.value.1.clone()));
+                let optional = self
+                    .engine
+                    .insert_type(TypeInfo::Optional(inner), Some(name.value.1.clone()));
+
+                self.engine.unify(value, optional, LinkReason::Binding);
+
+                self.enter_scope();
+                self.check_function_shadowing(name.value.clone());
+                self.check_reserved_name(name.value.0, &name.value.1);
+                self.scopes.insert_variable(
+                    name.value.0,
+                    Variable {
+                        type_id: inner,
+                        shadowable: true,
+                        mutable,
+                    },
+                );
+
+                let success = self.check_expression(*success, context);
+                self.exit_scope();
+
+                let failure = self.check_expression(*failure, context);
+
+                self.engine.unify(success, failure, LinkReason::Conditional);
+
+                success
+            }
+            Expression::Break { label, value } => {
                 let unit = self
                     .engine
-                    .insert_type(TypeInfo::Unit, Some(expression.1.clone()));
+                    .insert_type(TypeInfo::Unit, Some(value.1.clone()));
+
+                let loop_return = match label {
+                    Some(label) => self.search_label(label),
+                    None => {
+                        if let ScopeContext::FunctionLoop { loop_return, .. } = context {
+                            Some(loop_return)
+                        } else {
+                            self.errors.insert_error(Error::InvalidFlow {
+                                span: value.1.clone(),
+                                construct: "break",
+                            });
+
+                            None
+                        }
+                    }
+                };
 
-                if let ScopeContext::FunctionLoop { loop_return, .. } = context {
-                    let found_ret_ty = self.check_expression(*expression, context);
+                // The value is checked even when there is no loop to break from, so errors in it are still reported.
+                let found_ret_ty = self.check_expression(*value, context);
 
+                if let Some(loop_return) = loop_return {
                     self.engine
                         .unify(loop_return, found_ret_ty, LinkReason::Loop)
-                } else {
-                    self.errors.insert_error(Error::InvalidFlow {
-                        span: expression.1.clone(),
-                        construct: "break",
-                    });
                 }
 
                 unit
@@ -299,12 +770,51 @@ impl<'a> Checker<'a> {
                 let found_return_ty = self.check_expression(*expression, context);
 
                 self.engine
-                    .unify(function_ret_ty, found_return_ty, LinkReason::Return);
+                    .coerce(found_return_ty, function_ret_ty, LinkReason::Return);
 
                 unit
             }
-            Expression::Continue => {
-                if let ScopeContext::Function { .. } = context {
+            Expression::TypeOf(value) => {
+                let span = value.1.clone();
+                let value = self.check_expression(*value, context);
+
+                self.type_queries.push((value, span));
+
+                self.engine
+                    .insert_type(TypeInfo::String, Some(expression.1))
+            }
+            // The value is passed through as is, so the print has the same type as the value.
+            Expression::Dbg(value) => {
+                let span = value.1.clone();
+                let value = self.check_expression(*value, context);
+
+                self.debug_prints.push((value, span));
+
+                value
+            }
+            Expression::Assert(condition) => {
+                // Conditions which aren't constant can only be asserted at runtime.
+                let constant = self.evaluate_constant(&condition);
+
+                if let Some(Constant::Boolean(false)) = constant {
+                    self.errors.insert_error(Error::AssertionFailed {
+                        span: condition.1.clone(),
+                    });
+                }
+
+                let boolean = self
+                    .engine
+                    .insert_type(TypeInfo::Boolean, Some(condition.1.clone()));
+                let condition = self.check_expression(*condition, context);
+
+                self.engine.unify(condition, boolean, LinkReason::Condition);
+
+                self.engine.insert_type(TypeInfo::Unit, Some(expression.1))
+            }
+            Expression::Continue(label) => {
+                if let Some(label) = label {
+                    self.search_label(label);
+                } else if let ScopeContext::Function { .. } = context {
                     self.errors.insert_error(Error::InvalidFlow {
                         span: expression.1.clone(),
                         construct: "continue",
@@ -313,83 +823,171 @@ impl<'a> Checker<'a> {
 
                 self.engine.insert_type(TypeInfo::Unit, Some(expression.1))
             }
-            Expression::Loop(body) => {
+            Expression::Loop { label, body } => {
                 let loop_return = self
                     .engine
-                    .insert_type(TypeInfo::Unknown(false), Some(expression.1));
+                    .insert_type(TypeInfo::Unknown(Unknown::Inferable), Some(expression.1));
 
-                self.check_expression(
+                if let Some((label, _)) = label {
+                    self.labels.push((label, loop_return));
+                }
+
+                let result = self.check_expression(
                     *body,
                     ScopeContext::FunctionLoop {
                         function_return: context.get_function_ret_ty(),
                         loop_return,
                     },
-                )
+                );
+
+                if label.is_some() {
+                    self.labels.pop();
+                }
+
+                result
             }
             Expression::Error => self
                 .engine
-                .insert_type(TypeInfo::Unknown(true), Some(expression.1)),
+                .insert_type(TypeInfo::Unknown(Unknown::Errored), Some(expression.1)),
             Expression::Use(_) => self.engine.insert_type(TypeInfo::Unit, Some(expression.1)),
         }
     }
 
-    fn check_function(&mut self, function: Function, span: Span) -> TypeId {
-        // Parameter will be stored in a new scope, different than the function body scope so I can use `exit_scope` later to remove the parameters.
-        self.enter_scope();
+    // Declares the type of a function from it's signature alone. This is done before it's body is checked, so that calls
+    // to it which are checked before it's body, such as recursive ones, already see it's parameters and return type.
+    fn declare_function(&mut self, function: &Function) -> Signature {
         let mut parameters = Vec::with_capacity(function.parameters.len());
 
         for TypeHint {
-            value: (name, span),
+            value: (_, span),
             type_hint,
-        } in function.parameters
+        } in &function.parameters
         {
-            let type_id = self.engine.insert_type(
-                type_hint.into_ty(&self.modules.get(self.current_mod)),
-                Some(span),
-            );
+            let type_hint = type_hint
+                .clone()
+                .into_ty(&self.scopes, &mut self.engine, self.errors);
+
+            parameters.push(self.engine.insert_type(type_hint, Some(span.clone())));
+        }
+
+        let return_type_hint =
+            function
+                .name
+                .type_hint
+                .clone()
+                .into_ty(&self.scopes, &mut self.engine, self.errors);
+        let return_type = self
+            .engine
+            .insert_type(return_type_hint, Some(function.name.value.1.clone()));
+
+        let function_type = self.engine.insert_type(
+            TypeInfo::Function {
+                parameters: parameters.clone(),
+                return_type,
+            },
+            Some(function.name.value.1.clone()),
+        );
+
+        // Closures aren't in scope, so there is nothing to unify their type with.
+        if !is_closure_name(&function.name.value.0) {
+            self.unify_in_place(&Id::new_single(function.name.value.0), function_type);
+        }
 
-            parameters.push(type_id);
+        Signature {
+            parameters,
+            return_type,
+            function_type,
+        }
+    }
+
+    // Top-level functions are checked by `check_program`, so any function checked here is nested in another.
+    fn check_function(&mut self, function: Function, span: Span) -> TypeId {
+        let surrounding_function = self
+            .nested_function
+            .replace((self.engine.types.len(), function.name.value.1.clone()));
+        let signature = self.declare_function(&function);
+        let function_type = signature.function_type;
+        let is_closure = is_closure_name(&function.name.value.0);
+        let result = self.check_function_body(function, signature, span);
+
+        self.nested_function = surrounding_function;
+
+        // A closure is used as a value, unlike a function declared in a block.
+        if is_closure {
+            function_type
+        } else {
+            result
+        }
+    }
+
+    fn check_function_body(
+        &mut self,
+        function: Function,
+        signature: Signature,
+        span: Span,
+    ) -> TypeId {
+        // A function can't break out of the loops surrounding it, so their labels are hidden while checking it.
+        let labels = std::mem::take(&mut self.labels);
+        // Parameter will be stored in a new scope, different than the function body scope so I can use `exit_scope` later to remove the parameters.
+        self.enter_scope();
+
+        for (parameter, type_id) in function.parameters.into_iter().zip(signature.parameters) {
+            self.check_reserved_name(parameter.value.0, &parameter.value.1);
             self.scopes.insert_variable(
-                name,
+                parameter.value.0,
                 Variable {
                     type_id,
                     shadowable: true,
+                    mutable: false,
                 },
             );
         }
 
-        let given_return_type = self.engine.insert_type(
-            function
-                .name
-                .type_hint
-                .into_ty(&self.modules.get(self.current_mod)),
-            Some(function.name.value.1.clone()),
-        );
         let context = ScopeContext::Function {
-            return_type: given_return_type,
+            return_type: signature.return_type,
         };
 
         let actual_return_type = self.check_expression(*function.body, context);
-        self.engine
-            .unify(actual_return_type, given_return_type, LinkReason::Return);
-
-        let function_type = self.engine.insert_type(
-            TypeInfo::Function {
-                parameters,
-                return_type: given_return_type,
-            },
-            Some(function.name.value.1),
+        self.engine.coerce(
+            actual_return_type,
+            signature.return_type,
+            LinkReason::Return,
         );
 
         self.exit_scope();
+        self.labels = labels;
+
+        if let Some(entry_point) = &mut self.entry_point {
+            if entry_point.span == function.name.value.1 {
+                entry_point.function_type = Some(signature.function_type);
+            }
+        }
 
-        self.unify_in_place(&function.name.value.0, function_type);
         self.engine.insert_type(TypeInfo::Unit, Some(span))
     }
 
+    // Every function in the program is declared before any of them is checked, so that functions may call each other
+    // regardless of the order they are defined in.
     fn check_program(&mut self, program: Program) {
+        let signatures = program
+            .iter()
+            .filter_map(|(top_level, _)| match top_level {
+                TopLevel::Function(function) => Some(function),
+                _ => None,
+            })
+            .map(|function| self.declare_function(function))
+            .collect::<Vec<_>>();
+        let mut signatures = signatures.into_iter();
+
         for top_level in program {
-            self.check_top_level(top_level);
+            match top_level.0 {
+                TopLevel::Function(function) => {
+                    self.check_function_body(function, signatures.next().unwrap(), top_level.1);
+                }
+                _ => {
+                    self.check_top_level(top_level);
+                }
+            }
         }
     }
 
@@ -402,13 +1000,9 @@ impl<'a> Checker<'a> {
                      value: data,
                      type_hint,
                  }| {
-                    (
-                        data.0,
-                        self.engine.insert_type(
-                            type_hint.into_ty(&self.modules.get(self.current_mod)),
-                            Some(data.1),
-                        ),
-                    )
+                    let type_hint = type_hint.into_ty(&self.scopes, &mut self.engine, self.errors);
+
+                    (data.0, self.engine.insert_type(type_hint, Some(data.1)))
                 },
             )
             .collect();
@@ -416,15 +1010,149 @@ impl<'a> Checker<'a> {
         let data_type = self
             .engine
             .insert_type(TypeInfo::Structure(fields), Some(span.clone()));
-        self.unify_in_place(&structure.name.0, data_type);
+        self.unify_in_place(&Id::new_single(structure.name.0), data_type);
+
+        self.engine.insert_type(TypeInfo::Unit, Some(span))
+    }
+
+    fn check_enumeration(&mut self, enumeration: Enumeration, span: Span) -> TypeId {
+        let variants = enumeration
+            .variants
+            .into_iter()
+            .map(|(variant, payload)| {
+                (
+                    variant,
+                    payload
+                        .into_iter()
+                        .map(|(data_type, span)| {
+                            let data_type = (data_type, span.clone()).into_ty(
+                                &self.scopes,
+                                &mut self.engine,
+                                self.errors,
+                            );
+
+                            self.engine.insert_type(data_type, Some(span))
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let data_type = self.engine.insert_type(
+            TypeInfo::Enumeration(
+                variants
+                    .iter()
+                    .map(|((variant, _), payload)| (*variant, payload.clone()))
+                    .collect(),
+            ),
+            Some(span.clone()),
+        );
+        let name = Id::new_single(enumeration.name.0);
+
+        self.unify_in_place(&name, data_type);
+
+        let enumeration_type = self.search_id(&name).unwrap().type_id;
+
+        // The variants live in the scope created for them during gathering.
+        self.enter_scope();
+
+        for ((variant, span), payload) in variants {
+            let instance = self.engine.insert_type(
+                TypeInfo::Instance {
+                    structure: enumeration_type,
+                    origin: None,
+                },
+                Some(span.clone()),
+            );
+
+            let constructor = if payload.is_empty() {
+                instance
+            } else {
+                self.engine.insert_type(
+                    TypeInfo::Function {
+                        parameters: payload,
+                        return_type: instance,
+                    },
+                    Some(span),
+                )
+            };
+
+            self.unify_in_place(&Id::new_single(variant), constructor);
+        }
+
+        self.exit_scope();
 
         self.engine.insert_type(TypeInfo::Unit, Some(span))
     }
 
+    fn check_exhaustiveness(&mut self) {
+        for MatchCheck {
+            scrutinee,
+            covered,
+            span,
+        } in std::mem::take(&mut self.matches)
+        {
+            // If the scrutinee isn't an enumeration, an error was already reported when unifying it with the patterns.
+            if let &TypeInfo::Instance {
+                structure: type_id, ..
+            } = self.engine.get_resolved(scrutinee)
+            {
+                if let TypeInfo::Enumeration(variants) = self.engine.get_resolved(type_id) {
+                    let mut missing = variants
+                        .keys()
+                        .filter(|variant| !covered.contains(variant))
+                        .copied()
+                        .collect::<Vec<_>>();
+
+                    if !missing.is_empty() {
+                        missing.sort();
+
+                        self.errors
+                            .insert_error(Error::NonExhaustiveMatch { span, missing });
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_entry_point(&mut self) {
+        if let Some(EntryPoint {
+            span,
+            function_type: Some(function_type),
+        }) = self.entry_point.take()
+        {
+            let is_valid = match self.engine.get_resolved(function_type) {
+                &TypeInfo::Function {
+                    ref parameters,
+                    return_type,
+                } => {
+                    parameters.is_empty()
+                        && matches!(
+                            self.engine.get_resolved(return_type),
+                            TypeInfo::Unit
+                                | TypeInfo::Unknown(Unknown::Inferable | Unknown::Errored)
+                        )
+                }
+                _ => false,
+            };
+
+            if !is_valid {
+                self.errors
+                    .insert_error(Error::InvalidMainSignature(Element {
+                        value: self.into_concrete_ty(function_type),
+                        span,
+                    }));
+            }
+        }
+    }
+
     fn check_top_level(&mut self, top_level: (TopLevel, Span)) -> TypeId {
         match top_level.0 {
             TopLevel::Function(function) => self.check_function(function, top_level.1),
             TopLevel::Structure(structure) => self.check_structure(structure, top_level.1),
+            TopLevel::Enumeration(enumeration) => {
+                self.check_enumeration(enumeration, top_level.1)
+            }
             _ => self.engine.insert_type(TypeInfo::Unit, Some(top_level.1)),
         }
     }
@@ -459,46 +1187,373 @@ impl<'a> Checker<'a> {
         backtrace
     }
 
-    fn insert_constraint(&mut self, constraint: Constraint) {
-        self.constraints.push(constraint)
+    // A mismatch where only one side is optional, and it's value would have matched the other side, means a possibly
+    // null value was used without being unwrapped.
+    fn search_unwrapped_optional(&self, a: TypeId, b: TypeId) -> Option<(TypeId, Span)> {
+        let inner = |type_id: TypeId| match self.engine.get_resolved(type_id) {
+            &TypeInfo::Optional(inner) => Some(inner),
+            _ => None,
+        };
+
+        let optional = match (inner(a), inner(b)) {
+            (Some(inner), None) if self.engine.unifies(inner, b) => a,
+            (None, Some(inner)) if self.engine.unifies(a, inner) => b,
+            _ => return None,
+        };
+
+        self.engine
+            .get(optional)
+            .1
+            .clone()
+            .map(|span| (optional, span))
     }
 
-    fn into_concrete_ty(&self, type_id: TypeId) -> Type {
-        match self.engine.remove_ref(self.engine.get(type_id).0.clone()) {
-            TypeInfo::Unknown(_) => Type::Unknown,
-            TypeInfo::Reference(type_id) => {
-                Type::Reference(Box::new(self.into_concrete_ty(type_id)))
-            }
-            TypeInfo::Link { .. } => unreachable!(),
-            TypeInfo::Unit => Type::Unit,
-            TypeInfo::Integer => Type::Integer,
-            TypeInfo::Boolean => Type::Boolean,
-            TypeInfo::String => Type::String,
-            TypeInfo::Structure(fields) => Type::Structure(Fields::new(
-                fields
-                    .into_iter()
-                    .map(|(name, type_id)| (name, self.into_concrete_ty(type_id)))
-                    .collect(),
-            )),
-            TypeInfo::Instance(type_id) => Type::Instance(self.modules.search_type(type_id)),
-            TypeInfo::Function {
+    // The innermost loop with the label is the one referred to, since labels may be shadowed.
+    fn search_label(&mut self, label: (Name, Span)) -> Option<TypeId> {
+        let loop_return = self.labels.iter().rev().find_map(|&(other, type_id)| {
+            if other == label.0 {
+                Some(type_id)
+            } else {
+                None
+            }
+        });
+
+        if loop_return.is_none() {
+            self.errors.insert_error(Error::UnknownLabel(Element {
+                value: label.0,
+                span: label.1,
+            }));
+        }
+
+        loop_return
+    }
+
+    // Shadowing a function with a variable is allowed, but since the function can't be reached by it's name afterwards
+    // it's likely to be a mistake. The functions of the prelude are left out, since their names are common ones that
+    // programs can't be expected to avoid.
+    fn check_function_shadowing(&mut self, shadow: (Name, Span)) {
+        if let Some(type_id) = self
+            .scopes
+            .search_local_variable(shadow.0)
+            .map(|variable| variable.type_id)
+        {
+            if let (TypeInfo::Function { .. }, Some(original)) = (
+                self.engine.get_resolved(type_id),
+                self.engine.get(type_id).1.clone(),
+            ) {
+                self.errors.insert_warning(Warning::ShadowsFunction {
+                    name: shadow.0,
+                    original,
+                    shadow: shadow.1,
+                });
+            }
+        }
+    }
+
+    // Type hints always take the names of primitive types as the types, so a variable with one couldn't be hinted.
+    fn check_reserved_name(&mut self, name: Name, span: &Span) {
+        if PRIMITIVE_TYPES.contains(&name.as_str()) {
+            self.errors.insert_error(Error::ReservedTypeName {
+                name,
+                span: span.clone(),
+            });
+        }
+    }
+
+    // There is no backend to lower the queries into strings yet, so the resolved types are reported instead, which is
+    // what they are mostly useful for anyway.
+    fn resolve_type_queries(&mut self) {
+        for (value, span) in std::mem::take(&mut self.type_queries) {
+            self.errors.insert_warning(Warning::TypeQuery {
+                data_type: self.into_concrete_ty(value),
+                span,
+            });
+        }
+    }
+
+    // Only integers, booleans and strings can be printed for now. Values whose type is unknown were already reported.
+    fn check_debug_prints(&mut self) {
+        for (value, span) in std::mem::take(&mut self.debug_prints) {
+            match self.engine.get_resolved(value) {
+                TypeInfo::Integer
+                | TypeInfo::RangedInteger { .. }
+                | TypeInfo::SizedInteger(_)
+                | TypeInfo::Boolean
+                | TypeInfo::String
+                | TypeInfo::Unknown(_) => (),
+                _ => self.errors.insert_error(Error::Unsupported {
+                    construct: "printing a value that isn't an integer, boolean or string",
+                    span,
+                }),
+            }
+        }
+    }
+
+    // Conversions are generic, so an argument that isn't an integer is reported as a mismatch with one. Values whose type is
+    // unknown were already reported.
+    fn check_conversions(&mut self) {
+        for (value, span) in std::mem::take(&mut self.conversions) {
+            match self.engine.get_resolved(value) {
+                TypeInfo::Integer
+                | TypeInfo::RangedInteger { .. }
+                | TypeInfo::SizedInteger(_)
+                | TypeInfo::Unknown(_) => (),
+                _ => {
+                    let integer = self.engine.insert_type(TypeInfo::Integer, Some(span));
+
+                    self.engine.mismatches.push(Mismatch {
+                        a: value,
+                        b: integer,
+                        reason: LinkReason::Parameter,
+                    });
+                }
+            }
+        }
+    }
+
+    // Operators and builtins are only evaluated while their names aren't declared by the program.
+    fn evaluate_constant(&self, expression: &(Expression, Span)) -> Option<Constant> {
+        constant::evaluate(expression, &|builtin| {
+            self.scopes.search_variable(builtin).is_none()
+        })
+    }
+
+    // The builtins are defined in `builtin`, a name only refers to one while the program doesn't declare it. The type a
+    // generic builtin is generic over is inferred anew for every use.
+    fn builtin_type(&mut self, name: Name, span: &Span) -> Option<TypeId> {
+        let data_type = search_builtin(name.as_str())?;
+        let generic = self
+            .engine
+            .insert_type(TypeInfo::Unknown(Unknown::Inferable), Some(span.clone()));
+
+        Some(self.from_concrete_ty(&data_type, generic, span))
+    }
+
+    // The opposite of `into_concrete_ty`, every type in it is given the span, and every unknown is the generic type.
+    // Structures and enumerations are only known by their fields or variants, which builtins never use, so they are
+    // left to be inferred.
+    #[allow(clippy::wrong_self_convention)]
+    fn from_concrete_ty(&mut self, data_type: &Type, generic: TypeId, span: &Span) -> TypeId {
+        let data_type = match data_type {
+            Type::Unknown => return generic,
+            Type::Unit => TypeInfo::Unit,
+            Type::Integer => TypeInfo::Integer,
+            &Type::RangedInteger { min, max } => TypeInfo::RangedInteger { min, max },
+            &Type::SizedInteger(width) => TypeInfo::SizedInteger(width),
+            Type::Boolean => TypeInfo::Boolean,
+            Type::String => TypeInfo::String,
+            Type::Reference(inner) => {
+                TypeInfo::Reference(self.from_concrete_ty(inner, generic, span))
+            }
+            Type::Optional(inner) => {
+                TypeInfo::Optional(self.from_concrete_ty(inner, generic, span))
+            }
+            Type::Function {
                 parameters,
                 return_type,
-            } => Type::Function {
+            } => TypeInfo::Function {
                 parameters: parameters
-                    .into_iter()
-                    .map(|type_id| self.into_concrete_ty(type_id))
+                    .iter()
+                    .map(|parameter| self.from_concrete_ty(parameter, generic, span))
                     .collect(),
-                return_type: Box::new(self.into_concrete_ty(return_type)),
+                return_type: self.from_concrete_ty(return_type, generic, span),
+            },
+            Type::Instance(id) => match self.search_id(id) {
+                Some(symbol) => TypeInfo::Instance {
+                    structure: symbol.type_id,
+                    origin: None,
+                },
+                None => TypeInfo::Unknown(Unknown::Errored),
+            },
+            Type::Structure(_) | Type::Enumeration(_) => TypeInfo::Unknown(Unknown::Inferable),
+        };
+
+        self.engine.insert_type(data_type, Some(span.clone()))
+    }
+
+    // Strings can't be represented at runtime yet, so the length of a string can only be taken if it's constant, in
+    // which case the call is folded into it.
+    fn check_builtin_call(
+        &mut self,
+        function: &(Expression, Span),
+        parameters: &[(Expression, Span)],
+    ) {
+        let builtin = match &function.0 {
+            Expression::Id(id) => match id.as_single() {
+                Some(name) if self.scopes.search_variable(name).is_none() => name,
+                _ => return,
+            },
+            _ => return,
+        };
+
+        if let ("len", [value]) = (builtin.as_str(), parameters) {
+            if self.evaluate_constant(value).is_none() {
+                self.errors.insert_error(Error::Unsupported {
+                    construct: "the length of a string that isn't constant",
+                    span: value.1.clone(),
+                });
+            }
+        }
+    }
+
+    // Arithmetic on constant integers is folded while compiling, so an overflow in it would otherwise silently be left to
+    // happen at runtime.
+    fn check_overflow(
+        &mut self,
+        function: &(Expression, Span),
+        parameters: &[(Expression, Span)],
+        span: &Span,
+    ) {
+        if constant::overflows(function, parameters, &|builtin| {
+            self.scopes.search_variable(builtin).is_none()
+        }) {
+            self.errors
+                .insert_error(Error::ConstantOverflow { span: span.clone() });
+        }
+    }
+
+    // Constant integers are checked against the range of the type they are used as, any other value can only be checked
+    // at runtime.
+    fn check_range(&mut self, constant: Option<Element<i32>>, type_id: TypeId) {
+        let data_type = match self.engine.get_resolved(type_id) {
+            &TypeInfo::Optional(inner) => self.engine.get_resolved(inner),
+            data_type => data_type,
+        };
+
+        let (min, max, range) = match *data_type {
+            TypeInfo::RangedInteger { min, max } => (min, max, Type::RangedInteger { min, max }),
+            TypeInfo::SizedInteger(width) => match (width.range(), &constant) {
+                (Some((min, max)), _) => (min, max, Type::SizedInteger(width)),
+                (None, Some(constant)) => {
+                    self.errors.insert_error(Error::Unsupported {
+                        construct: "a constant of an integer wider than `Int`",
+                        span: constant.span.clone(),
+                    });
+
+                    return;
+                }
+                (None, None) => return,
             },
+            _ => return,
+        };
+
+        if let Some(constant) = constant {
+            if !(min..=max).contains(&constant.value) {
+                self.errors.insert_error(Error::OutOfRange {
+                    value: constant,
+                    range: OptElement {
+                        value: range,
+                        span: self.engine.get(type_id).1.clone(),
+                    },
+                });
+            }
+        }
+    }
+
+    // Scopes are entered in the same order they were created in while gathering, so they are found by counting.
+    fn enter_scope(&mut self) {
+        self.scopes.enter_scope();
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.exit_scope();
+    }
+
+    fn search_id(&self, id: &Id) -> Option<&Variable> {
+        self.scopes.search_id(id)
+    }
+
+    // Nested functions can't capture the locals of the functions surrounding them, since there is no closure environment
+    // to store them in. Returns the span of the nested function if the variable is such a local.
+    fn search_capture(&self, type_id: TypeId) -> Option<Span> {
+        match &self.nested_function {
+            Some((start, span)) if (self.first_local..*start).contains(&type_id) => {
+                Some(span.clone())
+            }
+            _ => None,
+        }
+    }
+
+    // Returns the type of the value behind the reference.
+    fn dereference(&mut self, reference: TypeId, span: Span) -> TypeId {
+        let value = self
+            .engine
+            .insert_type(TypeInfo::Unknown(Unknown::Inferable), Some(span.clone()));
+        let expected = self
+            .engine
+            .insert_type(TypeInfo::Reference(value), Some(span));
+
+        self.engine
+            .unify(reference, expected, LinkReason::Dereference);
+
+        value
+    }
+
+    // Calls are unified as the called function and then the function type built from the call,
+    // so a mismatch in their parameter count means the wrong amount of arguments was passed.
+    fn search_argument_count_mismatch(
+        &self,
+        function: TypeId,
+        call: TypeId,
+        reason: LinkReason,
+    ) -> Option<Error> {
+        if reason != LinkReason::Call {
+            return None;
+        }
+
+        match (
+            self.engine.get(self.engine.resolve(function)),
+            self.engine.get(self.engine.resolve(call)),
+        ) {
+            (
+                (
+                    TypeInfo::Function {
+                        parameters: expected,
+                        ..
+                    },
+                    Some(def_span),
+                ),
+                (
+                    TypeInfo::Function {
+                        parameters: found, ..
+                    },
+                    Some(call_span),
+                ),
+            ) if expected.len() != found.len() => Some(Error::ArgumentCountMismatch {
+                expected: expected.len(),
+                found: found.len(),
+                call_span: call_span.clone(),
+                def_span: def_span.clone(),
+            }),
+            _ => None,
         }
     }
 
+    // Follows links until reaching the type they lead to.
+    fn insert_constraint(&mut self, constraint: Constraint) {
+        self.constraints.push(constraint)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn into_concrete_ty(&self, type_id: TypeId) -> Type {
+        self.engine.into_concrete_ty(type_id, &|type_id| {
+            self.scopes.search_type_name_by_id(type_id)
+        })
+    }
+
     fn new(scopes: StaticScopes, engine: Engine, errors: &'a mut Errors) -> Self {
         Self {
             scopes,
+            first_local: engine.types.len(),
+            nested_function: None,
+            type_queries: Vec::new(),
+            debug_prints: Vec::new(),
+            conversions: Vec::new(),
             engine,
             constraints: Vec::new(),
+            matches: Vec::new(),
+            labels: Vec::new(),
+            entry_point: None,
             errors,
         }
     }
@@ -506,48 +1561,158 @@ impl<'a> Checker<'a> {
     // The result of the function represents if the constraint was solved or not.
     // Being "solved" means we either found the type of the field or found an error.
     fn solve_constraint(&mut self, constraint: Constraint) -> bool {
-        let info = self.engine.get(constraint.object_id).0.clone();
-        let access_span = self.engine.get(constraint.field_id).1.clone();
-
-        match self.engine.remove_ref(info) {
-            TypeInfo::Unknown(_) => return false,
-            TypeInfo::Instance(type_id) => {
-                match self.engine.remove_ref(self.engine.get(type_id).0.clone()) {
-                    TypeInfo::Structure(fields) => {
-                        if let Some(&type_id) = fields.get(&constraint.field) {
-                            self.engine
-                                .unify(constraint.field_id, type_id, LinkReason::Field);
-                            true
-                        } else {
-                            self.errors.insert_error(Error::MissingField {
-                                structure: Element {
-                                    value: self.into_concrete_ty(constraint.object_id),
-                                    span: access_span.unwrap(),
-                                },
-                                field_name: constraint.field,
-                            });
-                            true
+        match constraint {
+            Constraint::Field {
+                object_id,
+                field_id,
+                field,
+            } => self.solve_field_constraint(object_id, field_id, field),
+            Constraint::Method {
+                receiver_id,
+                method,
+                field_call,
+                free_call,
+                free_function,
+            } => {
+                let field = match self.engine.get_resolved(receiver_id) {
+                    TypeInfo::Unknown(Unknown::Inferable | Unknown::Errored) => return false,
+                    &TypeInfo::Instance { structure, .. } => {
+                        match self.engine.get_resolved(structure) {
+                            TypeInfo::Structure(fields) => fields.get(&method).copied(),
+                            _ => None,
                         }
                     }
+                    _ => None,
+                };
+
+                // A field always takes priority over a free function with the same name.
+                match (field, free_function) {
+                    (Some(field), _) => self.engine.unify(field, field_call, LinkReason::Call),
+                    (None, Some(function)) => {
+                        self.engine.unify(function, free_call, LinkReason::Call)
+                    }
+                    // Neither exist, which is reported as a missing field since fields are searched for first.
+                    (None, None) => {
+                        return self.solve_field_constraint(receiver_id, field_call, method)
+                    }
+                }
+
+                true
+            }
+            Constraint::Instance {
+                object,
+                structure,
+                fields,
+                span,
+            } => {
+                let declared = match self.engine.get_resolved(structure) {
+                    TypeInfo::Unknown(Unknown::Inferable) => return false,
+                    TypeInfo::Structure(declared) => declared.clone(),
+                    // Anything else is reported as a mismatch with the fields that were given.
                     _ => {
+                        let given_type = self.engine.insert_type(
+                            TypeInfo::Structure(
+                                fields
+                                    .into_iter()
+                                    .map(|(field, type_id, _)| (field, type_id))
+                                    .collect(),
+                            ),
+                            Some(span),
+                        );
+
+                        self.engine
+                            .unify(structure, given_type, LinkReason::Structure);
+
+                        return true;
+                    }
+                };
+
+                for &(field, type_id, ref field_span) in &fields {
+                    if let Some(&declaration) = declared.get(&field) {
+                        self.engine.unify(declaration, type_id, LinkReason::Field);
+                    } else {
                         self.errors.insert_error(Error::MissingField {
                             structure: Element {
-                                value: self.into_concrete_ty(constraint.object_id),
+                                value: Type::Instance(object.clone()),
+                                span: field_span.clone(),
+                            },
+                            field_name: field,
+                            instance: None,
+                        });
+                    }
+                }
+
+                // All omitted fields are reported together, instead of as a mismatch with the structure.
+                let mut missing = declared
+                    .keys()
+                    .filter(|&&field| !fields.iter().any(|&(given, ..)| given == field))
+                    .copied()
+                    .collect::<Vec<_>>();
+
+                if !missing.is_empty() {
+                    missing.sort();
+
+                    self.errors.insert_error(Error::MissingFields {
+                        structure: Element {
+                            value: object,
+                            span,
+                        },
+                        missing,
+                    });
+                }
+
+                true
+            }
+        }
+    }
+
+    fn solve_field_constraint(&mut self, object_id: TypeId, field_id: TypeId, field: Name) -> bool {
+        let access_span = self.engine.get(field_id).1.clone();
+
+        // The type is cloned since the origin of an instance is still needed after the engine is used again.
+        match self.engine.get_resolved(object_id).clone() {
+            TypeInfo::Unknown(Unknown::Inferable | Unknown::Errored) => false,
+            TypeInfo::Instance {
+                structure: type_id,
+                origin,
+            } => match self.engine.get_resolved(type_id) {
+                TypeInfo::Unknown(_) => false,
+                TypeInfo::Structure(fields) => {
+                    if let Some(&type_id) = fields.get(&field) {
+                        self.engine.unify(field_id, type_id, LinkReason::Field);
+                        true
+                    } else {
+                        self.errors.insert_error(Error::MissingField {
+                            structure: Element {
+                                value: self.into_concrete_ty(object_id),
                                 span: access_span.unwrap(),
                             },
-                            field_name: constraint.field,
+                            field_name: field,
+                            instance: origin,
                         });
                         true
                     }
                 }
-            }
+                _ => {
+                    self.errors.insert_error(Error::MissingField {
+                        structure: Element {
+                            value: self.into_concrete_ty(object_id),
+                            span: access_span.unwrap(),
+                        },
+                        field_name: field,
+                        instance: origin,
+                    });
+                    true
+                }
+            },
             _ => {
                 self.errors.insert_error(Error::MissingField {
                     structure: Element {
-                        value: self.into_concrete_ty(constraint.object_id),
+                        value: self.into_concrete_ty(object_id),
                         span: access_span.unwrap(),
                     },
-                    field_name: constraint.field,
+                    field_name: field,
+                    instance: None,
                 });
                 true
             }
@@ -563,8 +1728,8 @@ impl<'a> Checker<'a> {
                 .constraints
                 .clone()
                 .into_iter()
-                .filter(|&constraint| {
-                    let result = self.solve_constraint(constraint);
+                .filter(|constraint| {
+                    let result = self.solve_constraint(constraint.clone());
                     progressed = progressed || result;
 
                     !result
@@ -584,10 +1749,205 @@ impl<'a> Checker<'a> {
     }
 }
 
+fn constant_integer(expression: &(Expression, Span)) -> Option<Element<i32>> {
+    match expression.0 {
+        Expression::Int(value) => Some(Element {
+            value,
+            span: expression.1.clone(),
+        }),
+        _ => None,
+    }
+}
+
+// The entry module is the root file, or `main.bell` when compiling a directory.
+fn search_main(root_module: &Module) -> Option<Span> {
+    let program = match root_module {
+        Module::Program { program, .. } => program,
+        Module::Submodule { modules, .. } => modules.iter().find_map(|module| match module {
+            Module::Program { name, program } if name.as_str() == "main" => Some(program),
+            _ => None,
+        })?,
+    };
+
+    program.iter().find_map(|(top_level, _)| match top_level {
+        TopLevel::Function(function) if function.name.value.0.as_str() == "main" => {
+            Some(function.name.value.1.clone())
+        }
+        _ => None,
+    })
+}
+
 pub fn check(
     root_module: Module,
+    prelude: Program,
     GatherOut { engine, scopes }: GatherOut,
+    kind: ProjectKind,
     errors: &mut Errors,
-) -> Types {
-    Checker::new(scopes, engine, errors).check(root_module)
+) -> (Types, StaticScopes) {
+    progress::enter("checking", None);
+
+    Checker::new(StaticScopes::new(scopes.raw_scopes), engine, errors).check(
+        root_module,
+        prelude,
+        kind,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use camino::Utf8PathBuf;
+    use internment::Intern;
+
+    use crate::core::{
+        error::{Error, Errors, Warning},
+        file::Entry,
+        span::SourceMap,
+    };
+    use crate::front_end::module;
+    use crate::{check_module, ProjectKind};
+
+    fn file(path: &str, contents: &str) -> Entry {
+        Entry::File {
+            path: Intern::new(Utf8PathBuf::from(path)),
+            contents: contents.to_string(),
+        }
+    }
+
+    // Checks a project made of several files, which is held in memory instead of being read from a directory.
+    fn check_project(entries: Vec<Entry>) -> Errors {
+        let mut errors = Errors {
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            sources: SourceMap::new(),
+        };
+
+        let module = module::from(
+            Entry::Directory {
+                path: Intern::new(Utf8PathBuf::from("project")),
+                entries,
+            },
+            &mut errors,
+        );
+
+        match check_module(module, ProjectKind::Executable, errors) {
+            Ok((_, _, errors)) | Err(errors) => errors,
+        }
+    }
+
+    #[test]
+    fn mutually_recursive_functions_are_checked() {
+        let errors = check_project(vec![file(
+            "project/main.bell",
+            "func a() { b() }\n\nfunc b() { a() }\n\nfunc main() { a() }\n",
+        )]);
+
+        assert!(errors.errors.is_empty(), "{:?}", errors.errors);
+    }
+
+    #[test]
+    fn type_hints_name_structures_in_other_modules() {
+        let errors = check_project(vec![
+            file(
+                "project/main.bell",
+                "use shapes::origin\n\nfunc main() {\n    var point: shapes::Point = origin();\n}\n",
+            ),
+            file(
+                "project/shapes.bell",
+                "struct Point { x: Int }\n\nfunc origin() -> Point {\n    Point { x: 0 }\n}\n",
+            ),
+        ]);
+
+        assert!(errors.errors.is_empty(), "{:?}", errors.errors);
+    }
+
+    #[test]
+    fn type_hints_naming_missing_structures_are_reported() {
+        let errors = check_project(vec![file(
+            "project/main.bell",
+            "func main() {\n    var shape: Shape? = null;\n}\n",
+        )]);
+
+        assert!(
+            matches!(
+                errors.errors.as_slice(),
+                [Error::MissingId { id }] if id.value.to_string() == "Shape"
+            ),
+            "{:?}",
+            errors.errors
+        );
+    }
+
+    #[test]
+    fn instances_of_structures_declared_later_are_checked() {
+        let errors = check_project(vec![file(
+            "project/main.bell",
+            "func main() {\n    Point { x: 1 };\n}\n\nstruct Point { x: Int }\n",
+        )]);
+
+        assert!(errors.errors.is_empty(), "{:?}", errors.errors);
+
+        let errors = check_project(vec![file(
+            "project/main.bell",
+            "func main() {\n    Point { y: 1 };\n}\n\nstruct Point { x: Int }\n",
+        )]);
+
+        assert!(
+            matches!(
+                errors.errors.as_slice(),
+                [Error::MissingField { field_name, .. }, Error::MissingFields { missing, .. }]
+                    if field_name.as_str() == "y" && missing.len() == 1 && missing[0].as_str() == "x"
+            ),
+            "{:?}",
+            errors.errors
+        );
+    }
+
+    #[test]
+    fn only_optionals_of_the_expected_type_are_unwrapped() {
+        let errors = check_project(vec![file(
+            "project/main.bell",
+            "func double(value: Int?) -> Int {\n    value\n}\n\nfunc main() {}\n",
+        )]);
+
+        assert!(
+            matches!(errors.errors.as_slice(), [Error::UnwrappedOptional(_)]),
+            "{:?}",
+            errors.errors
+        );
+
+        let errors = check_project(vec![file(
+            "project/main.bell",
+            "func flag(value: Int?) -> Bool {\n    value\n}\n\nfunc main() {}\n",
+        )]);
+
+        assert!(
+            matches!(errors.errors.as_slice(), [Error::TypeMismatch { .. }]),
+            "{:?}",
+            errors.errors
+        );
+    }
+
+    #[test]
+    fn only_functions_of_the_program_are_shadowed() {
+        let errors = check_project(vec![file(
+            "project/main.bell",
+            "func main() {\n    var abs = 1;\n}\n",
+        )]);
+
+        assert!(errors.warnings.is_empty(), "{:?}", errors.warnings);
+
+        let errors = check_project(vec![file(
+            "project/main.bell",
+            "func twice() {}\n\nfunc main() {\n    var twice = 1;\n}\n",
+        )]);
+
+        assert!(
+            matches!(
+                errors.warnings.as_slice(),
+                [Warning::ShadowsFunction { name, .. }] if name.as_str() == "twice"
+            ),
+            "{:?}",
+            errors.warnings
+        );
+    }
 }
@@ -0,0 +1,122 @@
+use crate::core::{span::Span, Name};
+
+use super::hir::Expression;
+
+// A value which is known while compiling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Constant {
+    Int(i32),
+    Boolean(bool),
+    String(Name),
+}
+
+// Operators and builtins are calls to functions such as `add` or `len`, so they are only evaluated while `is_builtin`
+// says the name still refers to them rather than to a function the user declared. Anything else, including arithmetic
+// which would overflow or divide by zero, isn't constant and is left to be evaluated at runtime. Overflows are reported
+// separately using `overflows`.
+pub fn evaluate(
+    expression: &(Expression, Span),
+    is_builtin: &impl Fn(Name) -> bool,
+) -> Option<Constant> {
+    match &expression.0 {
+        Expression::Int(value) => Some(Constant::Int(*value)),
+        Expression::Boolean(value) => Some(Constant::Boolean(*value)),
+        Expression::String(value) => Some(Constant::String(*value)),
+        Expression::Block { expressions, tail } if expressions.is_empty() => {
+            evaluate(tail, is_builtin)
+        }
+        // Only the branch that is taken has to be constant.
+        Expression::Conditional {
+            condition,
+            success,
+            failure,
+        } => match evaluate(condition, is_builtin)? {
+            Constant::Boolean(true) => evaluate(success, is_builtin),
+            Constant::Boolean(false) => evaluate(failure, is_builtin),
+            _ => None,
+        },
+        Expression::Call {
+            function,
+            parameters: (parameters, _),
+        } => {
+            let builtin = match &function.0 {
+                Expression::Id(id) => id.as_single().filter(|&builtin| is_builtin(builtin))?,
+                _ => return None,
+            };
+
+            match parameters.as_slice() {
+                [value] => evaluate_builtin(builtin.as_str(), evaluate(value, is_builtin)?),
+                [left, right] => evaluate_operator(
+                    builtin.as_str(),
+                    evaluate(left, is_builtin)?,
+                    evaluate(right, is_builtin)?,
+                ),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// Whether a call is an arithmetic operator on constant integers whose result doesn't fit in an integer. The operands are
+// constant, so an operator around one that overflows isn't, meaning only the innermost overflow is found.
+pub fn overflows(
+    function: &(Expression, Span),
+    parameters: &[(Expression, Span)],
+    is_builtin: &impl Fn(Name) -> bool,
+) -> bool {
+    let operator = match &function.0 {
+        Expression::Id(id) => match id.as_single() {
+            Some(operator) if is_builtin(operator) => operator,
+            _ => return false,
+        },
+        _ => return false,
+    };
+
+    let (left, right) = match parameters {
+        [left, right] => match (evaluate(left, is_builtin), evaluate(right, is_builtin)) {
+            (Some(Constant::Int(left)), Some(Constant::Int(right))) => (left, right),
+            _ => return false,
+        },
+        _ => return false,
+    };
+
+    match operator.as_str() {
+        "add" => left.overflowing_add(right).1,
+        "subtract" => left.overflowing_sub(right).1,
+        "multiply" => left.overflowing_mul(right).1,
+        // Dividing by zero isn't an overflow, the only division that overflows is of the minimum by -1.
+        "divide" | "modulo" => right == -1 && left == i32::MIN,
+        _ => false,
+    }
+}
+
+fn evaluate_builtin(builtin: &str, value: Constant) -> Option<Constant> {
+    match (builtin, value) {
+        ("len", Constant::String(value)) => {
+            Some(Constant::Int(i32::try_from(value.chars().count()).ok()?))
+        }
+        _ => None,
+    }
+}
+
+fn evaluate_operator(operator: &str, left: Constant, right: Constant) -> Option<Constant> {
+    use Constant::{Boolean, Int};
+
+    Some(match (operator, left, right) {
+        ("add", Int(left), Int(right)) => Int(left.checked_add(right)?),
+        ("subtract", Int(left), Int(right)) => Int(left.checked_sub(right)?),
+        ("multiply", Int(left), Int(right)) => Int(left.checked_mul(right)?),
+        ("divide", Int(left), Int(right)) => Int(left.checked_div(right)?),
+        ("modulo", Int(left), Int(right)) => Int(left.checked_rem(right)?),
+        ("lesser", Int(left), Int(right)) => Boolean(left < right),
+        ("greater", Int(left), Int(right)) => Boolean(left > right),
+        ("lesser_or_equal", Int(left), Int(right)) => Boolean(left <= right),
+        ("greater_or_equal", Int(left), Int(right)) => Boolean(left >= right),
+        ("equal", left, right) => Boolean(left == right),
+        ("not_equal", left, right) => Boolean(left != right),
+        ("and", Boolean(left), Boolean(right)) => Boolean(left && right),
+        ("or", Boolean(left), Boolean(right)) => Boolean(left || right),
+        _ => return None,
+    })
+}
@@ -1,17 +1,24 @@
+use std::collections::HashMap;
+
 use crate::core::{
-    ast::Id,
+    ast::{is_closure_name, Id},
     error::{Element, Error, Errors},
+    progress,
     span::Span,
-    types::{Engine, LinkReason, ScopeConnection, Scopes, TypeInfo, Variable},
+    types::{Engine, LinkReason, ScopeConnection, Scopes, TypeInfo, Unknown, Variable},
+    Name,
 };
 
 use super::hir::{
-    self, AssignLocation, Expression, Function, Module, Program, Structure, TopLevel,
+    self, Arm, AssignLocation, Enumeration, Expression, Function, Import, Module, Program,
+    Structure, TopLevel,
 };
 
 struct Gatherer<'a> {
     scopes: Scopes,
     engine: Engine,
+    // The names imported into the current program, and where they were imported.
+    imports: HashMap<Name, Span>,
     errors: &'a mut Errors,
 }
 
@@ -20,42 +27,46 @@ impl<'a> Gatherer<'a> {
         Gatherer {
             scopes: Scopes::new(),
             engine: Engine::new(),
+            imports: HashMap::new(),
             errors,
         }
     }
 
-    // This code assumes the module has already been inserted into the scope tree, and is just populating it.
+    // This code assumes the module has already been inserted into the scope tree, and is just populating it with
+    // it's imports.
     fn gather_module(&mut self, module: &Module) {
-        match module {
-            Module::Program { name, program } => {
-                // We store the previous scope since we need to modify the module scope which is disconnected.
-                let previous_scope = self.scopes.current;
-
-                /*
-                This search will always work for two reasons:
+        // We store the previous scope since we need to modify the module scope which is disconnected.
+        let previous_scope = self.scopes.current;
 
-                1. This code can't go back to an earlier scope since the current scope is ALWAYS one of a module,
-                so it is always disconnected from the other scopes.
-                2. The module is assumed to be located at the current scope,
-                since a requirment of this function is that we already inserted it.
-                */
-                self.scopes.current = self.scopes.search_module(name).unwrap();
+        /*
+        This search will always work for two reasons:
 
-                self.gather_program(program);
+        1. This code can't go back to an earlier scope since the current scope is ALWAYS one of a module,
+        so it is always disconnected from the other scopes.
+        2. The module is assumed to be located at the current scope,
+        since a requirment of this function is that we already inserted it.
+        */
+        match module {
+            Module::Program { name, program } => {
+                self.scopes.current = self.scopes.search_module(*name).unwrap();
 
-                self.scopes.current = previous_scope;
+                self.gather_imports(program);
             }
             Module::Submodule { name, modules } => {
-                // We only gather imports in programs, so theres no need to keep track of the previous scope in here.
+                self.scopes.current = self.scopes.search_module(*name).unwrap();
+
                 for module in modules {
                     self.gather_module(module);
                 }
             }
         }
+
+        self.scopes.current = previous_scope;
     }
 
     // We need to first insert all modules, before populating them so that imports will work.
     // The problem is that imports can refer to other modules that haven't been populated yet, hence this code.
+    // The items of a program are gathered here, since the scopes they create must be in the order they are checked in.
     fn insert_hir_module(&mut self, module: &Module) {
         // This is the scope of the module that will later be populated.
         let module_scope = self
@@ -68,7 +79,7 @@ impl<'a> Gatherer<'a> {
 
                 self.scopes.exit_current_scope();
 
-                self.scopes.insert_module(name, module_scope);
+                self.scopes.insert_module(*name, module_scope);
             }
             Module::Submodule { name, modules } => {
                 for module in modules {
@@ -77,7 +88,7 @@ impl<'a> Gatherer<'a> {
 
                 self.scopes.exit_current_scope();
 
-                self.scopes.insert_module(name, module_scope);
+                self.scopes.insert_module(*name, module_scope);
             }
         }
     }
@@ -88,27 +99,57 @@ impl<'a> Gatherer<'a> {
         }
     }
 
+    fn gather_imports(&mut self, program: &Program) {
+        self.imports.clear();
+
+        for (top_level, _) in program {
+            if let TopLevel::Import(import) = top_level {
+                self.gather_import(import);
+            }
+        }
+    }
+
     fn gather_top_level(&mut self, top_level: &(TopLevel, Span)) {
         match &top_level.0 {
             TopLevel::Function(function) => self.gather_function(function),
             TopLevel::Structure(structure) => self.gather_structure(structure),
-            TopLevel::Import((id, span)) => self.gather_import(id, span),
+            TopLevel::Enumeration(enumeration) => self.gather_enumeration(enumeration),
+            // Imports are gathered once every module is inserted, since they may refer to any of them.
+            TopLevel::Import(_) => (),
         }
     }
 
     // This function actually gives priority to modules when importing IDs, however that shouldn't matter,
     // since the only situation when this could occur,
     // already disallows having modules have names already used by variables.
-    fn gather_import(&mut self, id: &Id, span: &Span) {
+    fn gather_import(
+        &mut self,
+        Import {
+            path: (id, span),
+            alias,
+        }: &Import,
+    ) {
+        let id_tail = id.leaf();
+        // An aliased import is only accessible by it's alias.
+        let (name, name_span) = alias.clone().unwrap_or_else(|| (id_tail, span.clone()));
+
+        if let Some(first) = self.imports.insert(name, name_span.clone()) {
+            self.errors.insert_error(Error::conflicting_ids(
+                first,
+                name_span,
+                Id::new_single(name),
+            ));
+
+            return;
+        }
+
         if self
             .scopes
             .get_id_origin_module(id)
             .and_then(|origin_module| {
-                let id_tail = *id.0.last().unwrap();
-
                 if let Some(scope_id) = self.scopes.raw_scopes.search_module(id_tail, origin_module)
                 {
-                    self.scopes.insert_module(id_tail, scope_id);
+                    self.scopes.insert_module(name, scope_id);
                     Some(())
                 } else if let Some(variable) = self
                     .scopes
@@ -116,7 +157,7 @@ impl<'a> Gatherer<'a> {
                     .search_variable(id_tail, origin_module)
                 {
                     self.scopes.insert_variable(
-                        id_tail,
+                        name,
                         Variable {
                             type_id: self.engine.insert_type(
                                 TypeInfo::Link {
@@ -126,6 +167,7 @@ impl<'a> Gatherer<'a> {
                                 Some(span.clone()),
                             ),
                             shadowable: false,
+                            mutable: false,
                         },
                     );
 
@@ -138,7 +180,7 @@ impl<'a> Gatherer<'a> {
         {
             self.errors.insert_error(Error::MissingId {
                 id: Element {
-                    value: id,
+                    value: id.clone(),
                     span: span.clone(),
                 },
             });
@@ -149,31 +191,83 @@ impl<'a> Gatherer<'a> {
         let (name, span) = structure.name.clone();
 
         if self.scopes.does_already_exist(name) {
-            self.errors.insert_error(Error::ConflictingIds {
-                first: span,
-                second: todo!(),
-                id: todo!(),
-            })
+            if let Some(existing) = self
+                .scopes
+                .search_variable(name)
+                .and_then(|variable| self.engine.get(variable.type_id).1.clone())
+            {
+                self.errors.insert_error(Error::conflicting_ids(
+                    existing,
+                    span.clone(),
+                    Id::new_single(name),
+                ));
+            }
         }
+
         self.scopes.insert_variable(
             name,
             Variable {
-                type_id: self.engine.insert_type(TypeInfo::Unknown(true), Some(span)),
+                type_id: self
+                    .engine
+                    .insert_type(TypeInfo::Unknown(Unknown::Inferable), Some(span)),
                 shadowable: false,
+                mutable: false,
             },
         );
     }
 
-    fn gather_function(&mut self, function: &Function) {
-        let (id, span) = function.name.value;
+    fn gather_enumeration(&mut self, enumeration: &Enumeration) {
+        let (name, span) = enumeration.name.clone();
+
+        // Variants are accessed like the items of a module (`Shape::Circle`), so they are stored in a scope of their own.
+        let enumeration_scope = self
+            .scopes
+            .create_scope(ScopeConnection::Exclusive(self.scopes.current));
 
+        for ((variant, span), _) in &enumeration.variants {
+            self.scopes.insert_variable(
+                *variant,
+                Variable {
+                    type_id: self
+                        .engine
+                        .insert_type(TypeInfo::Unknown(Unknown::Inferable), Some(span.clone())),
+                    shadowable: false,
+                    mutable: false,
+                },
+            );
+        }
+
+        self.scopes.exit_current_scope();
+
+        self.scopes.insert_module(name, enumeration_scope);
         self.scopes.insert_variable(
-            id,
+            name,
             Variable {
-                type_id: self.engine.insert_type(TypeInfo::Unknown(true), Some(span)),
+                type_id: self
+                    .engine
+                    .insert_type(TypeInfo::Unknown(Unknown::Inferable), Some(span)),
                 shadowable: false,
+                mutable: false,
             },
         );
+    }
+
+    fn gather_function(&mut self, function: &Function) {
+        let (id, span) = function.name.value.clone();
+
+        // A closure is only a value, so it's generated name is never put in scope.
+        if !is_closure_name(&id) {
+            self.scopes.insert_variable(
+                id,
+                Variable {
+                    type_id: self
+                        .engine
+                        .insert_type(TypeInfo::Unknown(Unknown::Inferable), Some(span)),
+                    shadowable: false,
+                    mutable: false,
+                },
+            );
+        }
 
         self.scopes.enter_scope();
         self.gather_expression(&function.body);
@@ -181,12 +275,18 @@ impl<'a> Gatherer<'a> {
     }
 
     fn gather_expression(&mut self, expression: &(Expression, Span)) {
+        progress::at(&expression.1);
+
         match &expression.0 {
             Expression::Function(function) => self.gather_function(function),
-            Expression::Instance { fields, .. } => {
+            Expression::Instance { fields, base, .. } => {
                 for (_, expression) in fields {
                     self.gather_expression(expression);
                 }
+
+                if let Some(base) = base {
+                    self.gather_expression(base);
+                }
             }
             Expression::Call {
                 function,
@@ -198,15 +298,30 @@ impl<'a> Gatherer<'a> {
                     self.gather_expression(parameter);
                 }
             }
+            Expression::MethodCall {
+                receiver,
+                parameters,
+                ..
+            } => {
+                self.gather_expression(receiver);
+
+                for parameter in &parameters.0 {
+                    self.gather_expression(parameter);
+                }
+            }
             Expression::Declaration { value, .. } => self.gather_expression(value),
             Expression::Assignment { to, from } => {
-                if let AssignLocation::Field { instance, .. } = &to.0 {
+                if let AssignLocation::Field { instance, .. }
+                | AssignLocation::Dereference(instance) = &to.0
+                {
                     self.gather_expression(instance);
                 }
 
                 self.gather_expression(from);
             }
             Expression::Access { from, .. } => self.gather_expression(from),
+            Expression::Reference(value) => self.gather_expression(value),
+            Expression::Dereference(reference) => self.gather_expression(reference),
             Expression::Block { expressions, tail } => {
                 self.scopes.enter_scope();
 
@@ -226,9 +341,40 @@ impl<'a> Gatherer<'a> {
                 self.gather_expression(success);
                 self.gather_expression(failure);
             }
-            Expression::Break(expression) => self.gather_expression(expression),
+            Expression::ConditionalBinding {
+                value,
+                success,
+                failure,
+                ..
+            } => {
+                self.gather_expression(value);
+
+                // The bound variable only exists in the success branch.
+                self.scopes
+                    .create_scope(ScopeConnection::Inclusive(self.scopes.current));
+                self.gather_expression(success);
+                self.scopes.exit_current_scope();
+
+                self.gather_expression(failure);
+            }
+            Expression::Enumeration(enumeration) => self.gather_enumeration(enumeration),
+            Expression::Match { scrutinee, arms } => {
+                self.gather_expression(scrutinee);
+
+                // Every arm gets it's own scope, since it may bind the payload of the variant.
+                for Arm { body, .. } in arms {
+                    self.scopes
+                        .create_scope(ScopeConnection::Inclusive(self.scopes.current));
+                    self.gather_expression(body);
+                    self.scopes.exit_current_scope();
+                }
+            }
+            Expression::Break { value, .. } => self.gather_expression(value),
             Expression::Return(expression) => self.gather_expression(expression),
-            Expression::Loop(expression) => self.gather_expression(expression),
+            Expression::TypeOf(value) | Expression::Assert(value) | Expression::Dbg(value) => {
+                self.gather_expression(value)
+            }
+            Expression::Loop { body, .. } => self.gather_expression(body),
             _ => (), // Some expression variants don't produce any items or scopes.
         }
     }
@@ -239,14 +385,44 @@ pub struct GatherOut {
     pub scopes: Scopes,
 }
 
-pub fn gather(module: hir::Module, errors: &mut Errors) -> GatherOut {
+// The prelude is gathered into the root scope, before any module.
+pub fn gather(module: &hir::Module, prelude: &Program, errors: &mut Errors) -> GatherOut {
+    progress::enter("gathering", None);
+
     let mut gatherer = Gatherer::new(errors);
 
-    gatherer.insert_hir_module(&module);
-    gatherer.gather_module(&module);
+    gatherer.gather_program(prelude);
+    gatherer.insert_hir_module(module);
+    gatherer.gather_module(module);
 
     GatherOut {
         engine: gatherer.engine,
         scopes: gatherer.scopes,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use camino::Utf8PathBuf;
+
+    use crate::core::error::Error;
+    use crate::{check_source, ProjectKind};
+
+    #[test]
+    fn conflicting_structures_label_the_earlier_declaration_first() {
+        let src = "struct Point { x: Int }\nstruct Point { y: Int }\nfunc main() {}\n";
+        let errors = check_source(Utf8PathBuf::from("main.bell"), src, ProjectKind::Executable)
+            .err()
+            .expect("the file should fail to check");
+
+        let (first, second) = errors
+            .iter()
+            .find_map(|error| match error {
+                Error::ConflictingIds { first, second, .. } => Some((first, second)),
+                _ => None,
+            })
+            .expect("the second structure should conflict with the first");
+
+        assert!(first.range.start < second.range.start);
+    }
+}
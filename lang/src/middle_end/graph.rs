@@ -0,0 +1,121 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use crate::core::{ast::Id, Name};
+
+use super::hir::{Module, TopLevel};
+
+type ModulePath = Vec<Name>;
+
+#[derive(Default)]
+struct Graph {
+    modules: BTreeSet<ModulePath>,
+    // A module and the module it's nested in.
+    nested: BTreeSet<(ModulePath, ModulePath)>,
+    // A program and the paths it imports.
+    imports: Vec<(ModulePath, Id)>,
+}
+
+impl Graph {
+    fn collect(&mut self, module: &Module, parent: &[Name]) {
+        let name = match module {
+            Module::Program { name, .. } | Module::Submodule { name, .. } => *name,
+        };
+
+        let mut path = parent.to_vec();
+        path.push(name);
+
+        if !parent.is_empty() {
+            self.nested.insert((parent.to_vec(), path.clone()));
+        }
+
+        match module {
+            Module::Program { program, .. } => {
+                for (top_level, _) in program {
+                    if let TopLevel::Import(import) = top_level {
+                        self.imports.push((path.clone(), import.path.0.clone()));
+                    }
+                }
+            }
+            Module::Submodule { modules, .. } => {
+                for module in modules {
+                    self.collect(module, &path);
+                }
+            }
+        }
+
+        self.modules.insert(path);
+    }
+
+    // Imports are resolved like the checker does it: the first part of the path is searched for in the importing module
+    // and then in each of the modules surrounding it, and the rest of the path goes down from there. The import either
+    // names a module itself or an item inside of one. Imports of the prelude, or of modules that don't exist, have no
+    // module to point to.
+    fn resolve(&self, importer: &[Name], id: &Id) -> Option<ModulePath> {
+        let module = |scope: &[Name], parts: &[Name]| {
+            let mut path = scope.to_vec();
+            path.extend_from_slice(parts);
+
+            self.modules.contains(&path).then_some(path)
+        };
+
+        let scope = (0..=importer.len())
+            .rev()
+            .map(|depth| &importer[..depth])
+            .find(|scope| module(scope, &[id.root()]).is_some())?;
+
+        module(scope, &id.0).or_else(|| module(scope, id.parent()))
+    }
+}
+
+fn node(path: &[Name]) -> String {
+    format!(
+        "\"{}\"",
+        path.iter()
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>()
+            .join("::")
+    )
+}
+
+// Renders the modules of a project as a Graphviz DOT graph. Nesting is drawn with dashed edges, and imports with solid
+// ones going from the importing module to the imported one.
+pub fn module_graph(module: &Module) -> String {
+    let mut graph = Graph::default();
+    graph.collect(module, &[]);
+
+    let imports = graph
+        .imports
+        .iter()
+        .filter_map(|(importer, id)| {
+            graph
+                .resolve(importer, id)
+                .filter(|imported| imported != importer)
+                .map(|imported| (importer.clone(), imported))
+        })
+        .collect::<BTreeSet<_>>();
+
+    let mut output = String::from("digraph modules {\n");
+
+    for module in &graph.modules {
+        writeln!(output, "    {};", node(module)).unwrap();
+    }
+
+    for (parent, module) in &graph.nested {
+        writeln!(
+            output,
+            "    {} -> {} [style = dashed];",
+            node(parent),
+            node(module)
+        )
+        .unwrap();
+    }
+
+    for (importer, imported) in &imports {
+        writeln!(output, "    {} -> {};", node(importer), node(imported)).unwrap();
+    }
+
+    output.push_str("}\n");
+
+    output
+}
@@ -1,9 +1,10 @@
 use std::collections::HashSet;
 
-use crate::core::span::Span;
+use crate::core::span::{Span, SpanOrigin};
 use crate::core::{
     ast::Id,
     error::{Element, Error, Errors, Pattern, Reason},
+    progress,
 };
 use crate::{
     ast::{self, TypeHint},
@@ -17,13 +18,33 @@ pub struct Function {
     pub body: Box<(Expression, Span)>,
 }
 
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub path: (Id, Span),
+    pub alias: Option<(Name, Span)>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Structure {
     pub name: (Name, Span),
     pub fields: Vec<TypeHint<(Name, Span)>>,
 }
 
+#[derive(Debug, Clone)]
+pub struct Enumeration {
+    pub name: (Name, Span),
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Arm {
+    pub variant: (Id, Span),
+    pub bindings: Vec<(Name, Span)>,
+    pub body: (Expression, Span),
+}
+
 type Field = ((Name, Span), (Expression, Span));
+type Variant = ((Name, Span), Vec<(ast::Type, Span)>);
 
 #[derive(Clone, Debug)]
 pub enum AssignLocation {
@@ -32,27 +53,38 @@ pub enum AssignLocation {
         instance: Box<(Expression, Span)>,
         field: (Name, Span),
     },
+    // Assigning through a reference, such as `*r = value`.
+    Dereference(Box<(Expression, Span)>),
 }
 
 #[derive(Debug, Clone)]
 pub enum Expression {
     Unit,
+    Null,
     Int(i32),
     Boolean(bool),
     String(Name),
-    Id(Name),
+    Id(Id),
     Function(Function),
     Instance {
-        object: (Name, Span),
+        object: (Id, Span),
         fields: Vec<Field>,
+        base: Option<Box<(Expression, Span)>>,
     },
     Call {
         function: Box<(Expression, Span)>,
         parameters: (Vec<(Expression, Span)>, Span),
     },
+    // `receiver.method(parameters)`, which calls the field `method` if it exists and otherwise calls `method` with the receiver as it's first argument.
+    MethodCall {
+        receiver: Box<(Expression, Span)>,
+        method: (Name, Span),
+        parameters: (Vec<(Expression, Span)>, Span),
+    },
     Declaration {
         name: TypeHint<(Name, Span)>,
         value: Box<(Expression, Span)>,
+        mutable: bool,
     },
     Assignment {
         to: (AssignLocation, Span),
@@ -62,21 +94,45 @@ pub enum Expression {
         from: Box<(Expression, Span)>,
         id: (Name, Span),
     },
+    Reference(Box<(Expression, Span)>),
+    Dereference(Box<(Expression, Span)>),
     Block {
         expressions: Vec<(Expression, Span)>,
         tail: Box<(Expression, Span)>,
     },
     Structure(Structure),
+    Enumeration(Enumeration),
     Conditional {
         condition: Box<(Expression, Span)>,
         success: Box<(Expression, Span)>,
         failure: Box<(Expression, Span)>,
     },
-    Break(Box<(Expression, Span)>),
+    // An `if var x = value` conditional, where `x` is only bound in the success branch if the value isn't null.
+    ConditionalBinding {
+        name: TypeHint<(Name, Span)>,
+        value: Box<(Expression, Span)>,
+        mutable: bool,
+        success: Box<(Expression, Span)>,
+        failure: Box<(Expression, Span)>,
+    },
+    Match {
+        scrutinee: Box<(Expression, Span)>,
+        arms: Vec<Arm>,
+    },
+    Break {
+        label: Option<(Name, Span)>,
+        value: Box<(Expression, Span)>,
+    },
     Return(Box<(Expression, Span)>),
-    Continue,
-    Loop(Box<(Expression, Span)>),
-    Use((Name, Span)),
+    Continue(Option<(Name, Span)>),
+    Loop {
+        label: Option<(Name, Span)>,
+        body: Box<(Expression, Span)>,
+    },
+    Use(Import),
+    TypeOf(Box<(Expression, Span)>),
+    Assert(Box<(Expression, Span)>),
+    Dbg(Box<(Expression, Span)>),
     Error,
 }
 
@@ -84,6 +140,7 @@ impl From<Expression> for &'static str {
     fn from(expression: Expression) -> Self {
         match expression {
             Expression::Unit => "unit",
+            Expression::Null => "null",
             Expression::Int(_) => "integer",
             Expression::Boolean(_) => "boolean",
             Expression::String(_) => "string",
@@ -91,16 +148,25 @@ impl From<Expression> for &'static str {
             Expression::Function(_) => "function",
             Expression::Instance { .. } => "instance",
             Expression::Call { .. } => "call",
+            Expression::MethodCall { .. } => "method call",
             Expression::Declaration { .. } => "declaration",
             Expression::Assignment { .. } => "assignment",
             Expression::Access { .. } => "field",
+            Expression::Reference(_) => "reference",
+            Expression::Dereference(_) => "dereference",
             Expression::Block { .. } => "block",
             Expression::Structure(_) => "structure",
+            Expression::Enumeration(_) => "enumeration",
             Expression::Conditional { .. } => "conditional",
-            Expression::Break(_) => "break",
+            Expression::ConditionalBinding { .. } => "conditional binding",
+            Expression::Match { .. } => "match",
+            Expression::Break { .. } => "break",
             Expression::Return(_) => "return",
-            Expression::Continue => "continue",
-            Expression::Loop(_) => "loop",
+            Expression::Continue(_) => "continue",
+            Expression::TypeOf(_) => "type query",
+            Expression::Assert(_) => "assertion",
+            Expression::Dbg(_) => "debug print",
+            Expression::Loop { .. } => "loop",
             Expression::Use(_) => "import",
             Expression::Error => "error",
         }
@@ -111,11 +177,13 @@ impl From<Expression> for &'static str {
 pub enum TopLevel {
     Function(Function),
     Structure(Structure),
-    Import((Id, Span)),
+    Enumeration(Enumeration),
+    Import(Import),
 }
 
 pub type Program = Vec<(TopLevel, Span)>;
 
+#[derive(Debug)]
 pub enum Module {
     Program { name: Name, program: Program },
     Submodule { name: Name, modules: Vec<Module> },
@@ -127,8 +195,12 @@ pub trait ToHir<T> {
 
 impl ToHir<(Expression, Span)> for (ast::Expression, Span) {
     fn to_hir(self, errors: &mut Errors) -> (Expression, Span) {
+        progress::at(&self.1);
+
         (
             match self.0 {
+                ast::Expression::Unit => Expression::Unit,
+                ast::Expression::Null => Expression::Null,
                 ast::Expression::Int(value) => Expression::Int(value),
                 ast::Expression::Boolean(value) => Expression::Boolean(value),
                 ast::Expression::String(value) => Expression::String(value),
@@ -138,70 +210,137 @@ impl ToHir<(Expression, Span)> for (ast::Expression, Span) {
                     parameters,
                     body,
                 } => Expression::Function(Function {
-                    name: name.map(|(name, span)| (Name::new_single(name), span), |ty| ty),
-                    parameters: parameters
-                        .into_iter()
-                        .map(|parameter| TypeHint {
-                            value: (Name::new_single(parameter.value.0), parameter.value.1),
-                            type_hint: parameter.type_hint,
-                        })
-                        .collect(),
+                    name,
+                    parameters,
                     body: Box::new(body.to_hir(errors)),
                 }),
-                ast::Expression::Instance { object, fields } => Expression::Instance {
+                ast::Expression::Instance {
+                    object,
+                    fields,
+                    base,
+                } => Expression::Instance {
                     object,
                     fields: fields
                         .into_iter()
                         .map(|(name, expression)| (name, expression.to_hir(errors)))
                         .collect(),
+                    base: base.map(|base| Box::new(base.to_hir(errors))),
                 },
                 ast::Expression::Call {
                     function,
                     parameters,
-                } => Expression::Call {
-                    function: Box::new(function.to_hir(errors)),
-                    parameters: (
+                } => {
+                    if is_chained_comparison(&function.0, &parameters.0) {
+                        errors.insert_error(Error::ChainedComparison {
+                            span: self.1.clone(),
+                        });
+
+                        return (Expression::Error, self.1);
+                    }
+
+                    let parameters = (
                         parameters
                             .0
                             .into_iter()
                             .map(|expression| expression.to_hir(errors))
                             .collect(),
                         parameters.1,
-                    ),
-                },
-                ast::Expression::Declaration { name, value } => Expression::Declaration {
-                    name: name.map(|(name, span)| (Name::new_single(name), span), |ty| ty),
+                    );
+
+                    // Whether this calls a field or a free function can only be known once the type of the receiver is.
+                    match function.0 {
+                        ast::Expression::Access { from, field } => Expression::MethodCall {
+                            receiver: Box::new(from.to_hir(errors)),
+                            method: field,
+                            parameters,
+                        },
+                        _ => Expression::Call {
+                            function: Box::new(function.to_hir(errors)),
+                            parameters,
+                        },
+                    }
+                }
+                ast::Expression::Declaration {
+                    name,
+                    value,
+                    mutable,
+                } => Expression::Declaration {
+                    name,
                     value: Box::new(value.to_hir(errors)),
+                    mutable,
                 },
-                ast::Expression::Assignment { to, from } => {
+                ast::Expression::Assignment { to, from, operator } => {
                     let to = to.to_hir(errors);
+                    let from = from.to_hir(errors);
 
-                    (|| Expression::Assignment {
-                        to: (
-                            match to.0 {
-                                Expression::Id(id) => AssignLocation::Variable(id),
-                                Expression::Access { from, id } => AssignLocation::Field {
-                                    instance: from,
-                                    field: id,
-                                },
-                                _ => {
+                    let from = match operator {
+                        // `a += b` is sugar for `a = a + b`, meaning the target is also read.
+                        Some((operator, operator_span)) => {
+                            // Reading and then writing a field or a dereference would evaluate the instance twice,
+                            // so it may only be something that has no side effects.
+                            if let Expression::Access { from: instance, .. }
+                            | Expression::Dereference(instance) = &to.0
+                            {
+                                if !is_place(instance) {
                                     errors.insert_error(Error::InvalidAssign(Element {
-                                        value: to.0.into(),
-                                        span: to.1.clone(),
+                                        value: instance.0.clone().into(),
+                                        span: instance.1.clone(),
                                     }));
 
-                                    return Expression::Error; // A kind of early return.
+                                    return (Expression::Error, self.1.clone());
                                 }
-                            },
-                            to.1,
-                        ),
-                        from: Box::new(from.to_hir(errors)),
-                    })()
+                            }
+
+                            (
+                                Expression::Call {
+                                    function: Box::new((Expression::Id(operator), operator_span)),
+                                    parameters: (vec![to.clone(), from], self.1.clone()),
+                                },
+                                self.1.clone(),
+                            )
+                        }
+                        None => from,
+                    };
+
+                    match to.0 {
+                        Expression::Id(id) if id.parent().is_empty() => Expression::Assignment {
+                            to: (AssignLocation::Variable(id.leaf()), to.1),
+                            from: Box::new(from),
+                        },
+                        Expression::Access { from: instance, id } => Expression::Assignment {
+                            to: (
+                                AssignLocation::Field {
+                                    instance,
+                                    field: id,
+                                },
+                                to.1,
+                            ),
+                            from: Box::new(from),
+                        },
+                        Expression::Dereference(reference) => Expression::Assignment {
+                            to: (AssignLocation::Dereference(reference), to.1),
+                            from: Box::new(from),
+                        },
+                        _ => {
+                            errors.insert_error(Error::InvalidAssign(Element {
+                                value: to.0.into(),
+                                span: to.1,
+                            }));
+
+                            Expression::Error
+                        }
+                    }
                 }
                 ast::Expression::Access { from, field: id } => Expression::Access {
                     from: Box::new(from.to_hir(errors)),
                     id,
                 },
+                ast::Expression::Reference(value) => {
+                    Expression::Reference(Box::new(value.to_hir(errors)))
+                }
+                ast::Expression::Dereference(reference) => {
+                    Expression::Dereference(Box::new(reference.to_hir(errors)))
+                }
                 ast::Expression::Block { expressions, tail } => Expression::Block {
                     expressions: expressions
                         .into_iter()
@@ -212,62 +351,163 @@ impl ToHir<(Expression, Span)> for (ast::Expression, Span) {
                         |expression| expression.to_hir(errors),
                     )),
                 },
-                ast::Expression::Structure { name, fields } => Expression::Structure(Structure {
-                    name: (Name::new_single(name.0), name.1),
-                    fields,
-                }),
+                ast::Expression::Structure { name, fields } => {
+                    Expression::Structure(Structure { name, fields })
+                }
+                ast::Expression::Enumeration { name, variants } => {
+                    Expression::Enumeration(Enumeration { name, variants })
+                }
+                ast::Expression::Match { scrutinee, arms } => Expression::Match {
+                    scrutinee: Box::new(scrutinee.to_hir(errors)),
+                    arms: arms
+                        .into_iter()
+                        .map(|(variant, bindings, body)| Arm {
+                            variant,
+                            bindings,
+                            body: body.to_hir(errors),
+                        })
+                        .collect(),
+                },
                 ast::Expression::Conditional { mut branches, tail } => {
                     let (condition, branch) = branches.remove(0);
 
-                    // Generate the total span of the rest of the conditional, if it exists.
+                    // Generate the total span of the rest of the conditional, if it exists. The else-ifs become a
+                    // conditional of their own, which is generated from the whole of this one.
                     let rest_span = match (branches.first(), branches.last()) {
-                        (Some(((_, span_a), _)), Some((_, (_, span_b)))) => Some(Span {
-                            path: span_a.path,
-                            range: span_a.range.start..span_b.range.end,
-                        }),
+                        (Some(((_, span_a), _)), Some((_, (_, span_b)))) => Some(
+                            Span {
+                                path: span_a.path,
+                                range: span_a.range.start..span_b.range.end,
+                                origin: SpanOrigin::Written,
+                            }
+                            .generated_from(&self.1),
+                        ),
                         _ => None,
                     };
 
-                    Expression::Conditional {
-                        condition: Box::new(condition.to_hir(errors)),
-                        failure: Box::new(
-                            (if let Some(span) = rest_span {
-                                (ast::Expression::Conditional { branches, tail }, span)
-                            } else if let Some(tail) = tail {
-                                *tail
-                            } else {
-                                // The span of this unit expression can be thought of being the span of the else-if,
-                                // since it's the reason this expression exists. (Kind of-ish).
-                                (
-                                    ast::Expression::Block {
-                                        expressions: Vec::new(),
-                                        tail: None,
-                                    },
-                                    branch.1.clone(),
-                                )
-                            })
-                            .to_hir(errors),
-                        ),
-                        success: Box::new(branch.to_hir(errors)),
+                    let failure = Box::new(
+                        (if let Some(span) = rest_span {
+                            (ast::Expression::Conditional { branches, tail }, span)
+                        } else if let Some(tail) = tail {
+                            *tail
+                        } else {
+                            // The span of this unit expression can be thought of being the span of the else-if,
+                            // since it's the reason this expression exists. (Kind of-ish).
+                            (
+                                ast::Expression::Block {
+                                    expressions: Vec::new(),
+                                    tail: None,
+                                },
+                                branch.1.clone().generated_from(&self.1),
+                            )
+                        })
+                        .to_hir(errors),
+                    );
+                    let success = Box::new(branch.to_hir(errors));
+
+                    match condition.0 {
+                        ast::Expression::Declaration {
+                            name,
+                            value,
+                            mutable,
+                        } => Expression::ConditionalBinding {
+                            name,
+                            value: Box::new(value.to_hir(errors)),
+                            mutable,
+                            success,
+                            failure,
+                        },
+                        _ => Expression::Conditional {
+                            condition: Box::new(condition.to_hir(errors)),
+                            success,
+                            failure,
+                        },
                     }
                 }
-                ast::Expression::Break(value) => Expression::Break(Box::new(value.to_hir(errors))),
+                ast::Expression::Break { label, value } => Expression::Break {
+                    label,
+                    value: Box::new(value.to_hir(errors)),
+                },
                 ast::Expression::Return(value) => {
                     Expression::Return(Box::new(value.to_hir(errors)))
                 }
-                ast::Expression::Continue => Expression::Continue,
-                ast::Expression::Loop(body) => Expression::Loop(Box::new(body.to_hir(errors))),
+                ast::Expression::Continue(label) => Expression::Continue(label),
+                ast::Expression::TypeOf(value) => {
+                    Expression::TypeOf(Box::new(value.to_hir(errors)))
+                }
+                ast::Expression::Assert(condition) => {
+                    Expression::Assert(Box::new(condition.to_hir(errors)))
+                }
+                ast::Expression::Dbg(value) => Expression::Dbg(Box::new(value.to_hir(errors))),
+                ast::Expression::Loop { label, body } => Expression::Loop {
+                    label,
+                    body: Box::new(body.to_hir(errors)),
+                },
                 ast::Expression::Error => Expression::Error,
                 // Use expressions are removed in the HIR, so this is gathered for origin information and then replaced with a "pass".
-                ast::Expression::Import(id) => Expression::Use(id),
+                ast::Expression::Import { path, alias } => Expression::Use(Import { path, alias }),
             },
             self.1,
         )
     }
 }
 
+// Whether an expression can be evaluated more than once without changing the program's behaviour.
+fn is_place(expression: &(Expression, Span)) -> bool {
+    match &expression.0 {
+        Expression::Id(_) => true,
+        Expression::Access { from, .. } | Expression::Dereference(from) => is_place(from),
+        _ => false,
+    }
+}
+
+fn is_comparison(function: &ast::Expression) -> bool {
+    match function {
+        ast::Expression::Identifier(Id(id)) => matches!(
+            id.as_slice(),
+            [operator] if [
+                "lesser",
+                "greater",
+                "lesser_or_equal",
+                "greater_or_equal",
+                "equal",
+                "not_equal",
+            ]
+            .contains(&operator.as_str())
+        ),
+        _ => false,
+    }
+}
+
+// Whether this is a comparison whose left operand is another comparison, such as `a < b < c`. A comparison in
+// parentheses is left alone, since the span of a grouping includes it's parentheses and so starts before the span of it's
+// left operand.
+fn is_chained_comparison(
+    function: &ast::Expression,
+    parameters: &[(ast::Expression, Span)],
+) -> bool {
+    match parameters.first() {
+        Some((
+            ast::Expression::Call {
+                function: inner,
+                parameters: (inner_parameters, _),
+            },
+            span,
+        )) => {
+            is_comparison(function)
+                && is_comparison(&inner.0)
+                && inner_parameters
+                    .first()
+                    .is_some_and(|left| left.1.range.start == span.range.start)
+        }
+        _ => false,
+    }
+}
+
 impl ToHir<Program> for Vec<(ast::Expression, Span)> {
     fn to_hir(self, errors: &mut Errors) -> Program {
+        progress::enter("lowering", None);
+
         self.into_iter()
             .filter_map(|expression| {
                 let expression = expression.to_hir(errors);
@@ -279,7 +519,10 @@ impl ToHir<Program> for Vec<(ast::Expression, Span)> {
                     Expression::Structure(structure) => {
                         Some((TopLevel::Structure(structure), expression.1))
                     }
-                    Expression::Use(id) => Some((TopLevel::Import(id), expression.1)),
+                    Expression::Enumeration(enumeration) => {
+                        Some((TopLevel::Enumeration(enumeration), expression.1))
+                    }
+                    Expression::Use(import) => Some((TopLevel::Import(import), expression.1)),
                     _ => {
                         errors.insert_error(Error::Unexpected {
                             expected: HashSet::from_iter([Pattern::Construct(
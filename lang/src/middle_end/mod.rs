@@ -1,3 +1,6 @@
+pub mod builtin;
 pub mod check;
+pub mod constant;
 pub mod gather;
+pub mod graph;
 pub mod hir;